@@ -0,0 +1,4 @@
+fn main() {
+    // The migrations are embedded in the binary, rebuild when they change
+    println!("cargo:rerun-if-changed=migrations");
+}
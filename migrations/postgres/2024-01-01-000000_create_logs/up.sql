@@ -0,0 +1,7 @@
+CREATE TABLE logs (
+    id SERIAL PRIMARY KEY,
+    status TEXT NOT NULL,
+    activity TEXT NOT NULL,
+    user_id BIGINT NOT NULL,
+    unix_time BIGINT NOT NULL
+);
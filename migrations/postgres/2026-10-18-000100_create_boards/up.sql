@@ -0,0 +1,7 @@
+CREATE TABLE boards (
+    guild_id BIGINT PRIMARY KEY NOT NULL,
+    channel_id BIGINT NOT NULL,
+    message_id BIGINT,
+    include_activities TEXT NOT NULL DEFAULT '',
+    exclude_activities TEXT NOT NULL DEFAULT ''
+);
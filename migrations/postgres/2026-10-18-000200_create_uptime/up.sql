@@ -0,0 +1,5 @@
+CREATE TABLE uptime (
+    id SERIAL PRIMARY KEY,
+    started_at BIGINT NOT NULL,
+    last_seen BIGINT NOT NULL
+);
@@ -0,0 +1,22 @@
+CREATE TABLE alert_rules (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    creator_id BIGINT NOT NULL,
+    target_id BIGINT,
+    activity TEXT,
+    status TEXT,
+    min_secs BIGINT NOT NULL,
+    channel_id BIGINT,
+    created_at BIGINT NOT NULL
+);
+
+CREATE TABLE alert_history (
+    id SERIAL PRIMARY KEY,
+    rule_id INTEGER NOT NULL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    streak_start BIGINT NOT NULL,
+    fired_at BIGINT NOT NULL
+);
+
+CREATE INDEX alert_history_guild_id_fired_at ON alert_history (guild_id, fired_at);
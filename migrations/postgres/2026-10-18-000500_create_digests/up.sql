@@ -0,0 +1,7 @@
+CREATE TABLE digests (
+    guild_id BIGINT PRIMARY KEY NOT NULL,
+    channel_id BIGINT NOT NULL,
+    schedule TEXT NOT NULL,
+    timezone TEXT NOT NULL,
+    last_sent BIGINT NOT NULL
+);
@@ -0,0 +1,12 @@
+CREATE TABLE audit (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT,
+    invoker_id BIGINT NOT NULL,
+    command TEXT NOT NULL,
+    options TEXT NOT NULL,
+    target_id BIGINT,
+    unix_time BIGINT NOT NULL
+);
+
+CREATE INDEX audit_invoker_id ON audit (invoker_id);
+CREATE INDEX audit_target_id ON audit (target_id);
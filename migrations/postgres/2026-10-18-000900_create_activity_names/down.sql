@@ -0,0 +1,9 @@
+ALTER TABLE logs ADD COLUMN activity TEXT;
+UPDATE logs SET activity = activity_names.name
+FROM activity_names WHERE activity_names.id = logs.activity_id;
+ALTER TABLE logs ALTER COLUMN activity SET NOT NULL;
+
+DROP INDEX logs_user_id_unix_time;
+DROP INDEX logs_activity_id_unix_time;
+ALTER TABLE logs DROP COLUMN activity_id;
+DROP TABLE activity_names;
@@ -0,0 +1,21 @@
+-- Every activity name is stored once, logs refer to it by id. No activity is the empty name.
+CREATE TABLE activity_names (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    first_seen BIGINT NOT NULL,
+    last_seen BIGINT NOT NULL
+);
+
+INSERT INTO activity_names (name, first_seen, last_seen)
+SELECT activity, MIN(unix_time), MAX(unix_time) FROM logs
+GROUP BY activity
+ORDER BY MIN(unix_time);
+
+ALTER TABLE logs ADD COLUMN activity_id INTEGER REFERENCES activity_names (id);
+UPDATE logs SET activity_id = activity_names.id
+FROM activity_names WHERE activity_names.name = logs.activity;
+ALTER TABLE logs ALTER COLUMN activity_id SET NOT NULL;
+ALTER TABLE logs DROP COLUMN activity;
+
+CREATE INDEX logs_user_id_unix_time ON logs (user_id, unix_time);
+CREATE INDEX logs_activity_id_unix_time ON logs (activity_id, unix_time);
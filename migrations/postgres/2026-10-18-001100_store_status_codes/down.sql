@@ -0,0 +1,9 @@
+ALTER TABLE logs ALTER COLUMN status TYPE TEXT USING
+    CASE status
+        WHEN 1 THEN 'online'
+        WHEN 2 THEN 'idle'
+        WHEN 3 THEN 'dnd'
+        WHEN 4 THEN 'offline'
+        WHEN 5 THEN 'invisible'
+        ELSE 'unknown'
+    END;
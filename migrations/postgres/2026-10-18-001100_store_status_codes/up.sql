@@ -0,0 +1,10 @@
+-- Statuses become codes: 1 online, 2 idle, 3 dnd, 4 offline, 5 invisible, 0 anything else
+ALTER TABLE logs ALTER COLUMN status TYPE SMALLINT USING
+    CASE status
+        WHEN 'online' THEN 1
+        WHEN 'idle' THEN 2
+        WHEN 'dnd' THEN 3
+        WHEN 'offline' THEN 4
+        WHEN 'invisible' THEN 5
+        ELSE 0
+    END;
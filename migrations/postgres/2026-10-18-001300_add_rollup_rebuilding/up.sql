@@ -0,0 +1,3 @@
+-- Set while the rollups are rebuilt day by day, sealed_until then being the end of the
+-- days rebuilt so far
+ALTER TABLE rollup_state ADD COLUMN rebuilding BOOLEAN NOT NULL DEFAULT FALSE;
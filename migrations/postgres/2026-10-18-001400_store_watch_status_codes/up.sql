@@ -0,0 +1,11 @@
+-- Watched statuses become the codes logs use: 1 online, 2 idle, 3 dnd, 4 offline, 5 invisible
+ALTER TABLE watches ALTER COLUMN status TYPE SMALLINT USING
+    CASE
+        WHEN status IS NULL THEN NULL
+        WHEN status = 'online' THEN 1
+        WHEN status = 'idle' THEN 2
+        WHEN status = 'dnd' THEN 3
+        WHEN status = 'offline' THEN 4
+        WHEN status = 'invisible' THEN 5
+        ELSE 0
+    END;
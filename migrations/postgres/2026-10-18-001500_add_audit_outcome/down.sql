@@ -0,0 +1 @@
+ALTER TABLE audit DROP COLUMN outcome;
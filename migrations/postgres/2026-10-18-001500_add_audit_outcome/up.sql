@@ -0,0 +1,3 @@
+-- Whether the invocation was served, or turned away as 'denied' by the allow list or as
+-- 'rate_limited'. Entries recorded before only covered served invocations.
+ALTER TABLE audit ADD COLUMN outcome TEXT NOT NULL DEFAULT 'ok';
@@ -0,0 +1,8 @@
+-- Databases prepared by hand before migrations existed already have this table
+CREATE TABLE IF NOT EXISTS logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status TEXT NOT NULL,
+    activity TEXT NOT NULL,
+    user_id BIGINT NOT NULL,
+    unix_time BIGINT NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE uptime (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    started_at BIGINT NOT NULL,
+    last_seen BIGINT NOT NULL
+);
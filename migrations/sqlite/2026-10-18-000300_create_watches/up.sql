@@ -0,0 +1,17 @@
+CREATE TABLE watches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    subscriber_id BIGINT NOT NULL,
+    target_id BIGINT NOT NULL,
+    activity TEXT,
+    status TEXT,
+    channel_id BIGINT,
+    last_notified BIGINT
+);
+
+CREATE INDEX watches_target_id ON watches (target_id);
+
+CREATE TABLE opt_outs (
+    user_id BIGINT PRIMARY KEY NOT NULL,
+    opted_out_at BIGINT NOT NULL
+);
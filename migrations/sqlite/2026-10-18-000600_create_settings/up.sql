@@ -0,0 +1,9 @@
+CREATE TABLE guild_settings (
+    guild_id BIGINT PRIMARY KEY NOT NULL,
+    timezone TEXT
+);
+
+CREATE TABLE user_settings (
+    user_id BIGINT PRIMARY KEY NOT NULL,
+    timezone TEXT
+);
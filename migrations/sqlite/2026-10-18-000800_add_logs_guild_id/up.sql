@@ -0,0 +1,2 @@
+-- Logs recorded before this migration keep a NULL guild
+ALTER TABLE logs ADD COLUMN guild_id BIGINT;
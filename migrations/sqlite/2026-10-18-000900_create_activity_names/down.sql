@@ -0,0 +1,16 @@
+CREATE TABLE logs_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status TEXT NOT NULL,
+    activity TEXT NOT NULL,
+    user_id BIGINT NOT NULL,
+    unix_time BIGINT NOT NULL,
+    guild_id BIGINT
+);
+
+INSERT INTO logs_old (id, status, activity, user_id, unix_time, guild_id)
+SELECT logs.id, logs.status, activity_names.name, logs.user_id, logs.unix_time, logs.guild_id
+FROM logs JOIN activity_names ON activity_names.id = logs.activity_id;
+
+DROP TABLE logs;
+ALTER TABLE logs_old RENAME TO logs;
+DROP TABLE activity_names;
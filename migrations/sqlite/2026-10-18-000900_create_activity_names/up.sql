@@ -0,0 +1,32 @@
+-- Every activity name is stored once, logs refer to it by id. No activity is the empty name.
+CREATE TABLE activity_names (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    first_seen BIGINT NOT NULL,
+    last_seen BIGINT NOT NULL
+);
+
+INSERT INTO activity_names (name, first_seen, last_seen)
+SELECT activity, MIN(unix_time), MAX(unix_time) FROM logs
+GROUP BY activity
+ORDER BY MIN(unix_time);
+
+-- SQLite can't change the columns of a table in place, so logs are copied into a new one
+CREATE TABLE logs_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status TEXT NOT NULL,
+    activity_id INTEGER NOT NULL REFERENCES activity_names (id),
+    user_id BIGINT NOT NULL,
+    unix_time BIGINT NOT NULL,
+    guild_id BIGINT
+);
+
+INSERT INTO logs_new (id, status, activity_id, user_id, unix_time, guild_id)
+SELECT logs.id, logs.status, activity_names.id, logs.user_id, logs.unix_time, logs.guild_id
+FROM logs JOIN activity_names ON activity_names.name = logs.activity;
+
+DROP TABLE logs;
+ALTER TABLE logs_new RENAME TO logs;
+
+CREATE INDEX logs_user_id_unix_time ON logs (user_id, unix_time);
+CREATE INDEX logs_activity_id_unix_time ON logs (activity_id, unix_time);
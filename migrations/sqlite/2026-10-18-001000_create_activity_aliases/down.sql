@@ -0,0 +1,3 @@
+DROP INDEX activity_names_canonical;
+ALTER TABLE activity_names DROP COLUMN canonical;
+DROP TABLE activity_aliases;
@@ -0,0 +1,13 @@
+CREATE TABLE activity_aliases (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    pattern TEXT NOT NULL,
+    is_regex BOOLEAN NOT NULL,
+    canonical TEXT NOT NULL,
+    creator_id BIGINT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+-- The name logs are grouped under, the raw name itself until an alias rule matches it
+ALTER TABLE activity_names ADD COLUMN canonical TEXT NOT NULL DEFAULT '';
+UPDATE activity_names SET canonical = name;
+CREATE INDEX activity_names_canonical ON activity_names (canonical);
@@ -0,0 +1,27 @@
+CREATE TABLE logs_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status TEXT NOT NULL,
+    activity_id INTEGER NOT NULL REFERENCES activity_names (id),
+    user_id BIGINT NOT NULL,
+    unix_time BIGINT NOT NULL,
+    guild_id BIGINT
+);
+
+INSERT INTO logs_old (id, status, activity_id, user_id, unix_time, guild_id)
+SELECT id,
+    CASE status
+        WHEN 1 THEN 'online'
+        WHEN 2 THEN 'idle'
+        WHEN 3 THEN 'dnd'
+        WHEN 4 THEN 'offline'
+        WHEN 5 THEN 'invisible'
+        ELSE 'unknown'
+    END,
+    activity_id, user_id, unix_time, guild_id
+FROM logs;
+
+DROP TABLE logs;
+ALTER TABLE logs_old RENAME TO logs;
+
+CREATE INDEX logs_user_id_unix_time ON logs (user_id, unix_time);
+CREATE INDEX logs_activity_id_unix_time ON logs (activity_id, unix_time);
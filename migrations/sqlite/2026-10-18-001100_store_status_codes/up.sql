@@ -0,0 +1,28 @@
+-- Statuses become codes: 1 online, 2 idle, 3 dnd, 4 offline, 5 invisible, 0 anything else
+CREATE TABLE logs_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    status SMALLINT NOT NULL,
+    activity_id INTEGER NOT NULL REFERENCES activity_names (id),
+    user_id BIGINT NOT NULL,
+    unix_time BIGINT NOT NULL,
+    guild_id BIGINT
+);
+
+INSERT INTO logs_new (id, status, activity_id, user_id, unix_time, guild_id)
+SELECT id,
+    CASE status
+        WHEN 'online' THEN 1
+        WHEN 'idle' THEN 2
+        WHEN 'dnd' THEN 3
+        WHEN 'offline' THEN 4
+        WHEN 'invisible' THEN 5
+        ELSE 0
+    END,
+    activity_id, user_id, unix_time, guild_id
+FROM logs;
+
+DROP TABLE logs;
+ALTER TABLE logs_new RENAME TO logs;
+
+CREATE INDEX logs_user_id_unix_time ON logs (user_id, unix_time);
+CREATE INDEX logs_activity_id_unix_time ON logs (activity_id, unix_time);
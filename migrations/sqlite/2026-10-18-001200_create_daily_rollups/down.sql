@@ -0,0 +1,3 @@
+DROP TABLE rollup_state;
+DROP TABLE daily_activity;
+DROP TABLE daily_status;
@@ -0,0 +1,25 @@
+-- Seconds per UTC day spent online, per user and status and per user and activity, kept
+-- up to date as logs come in. Logs recorded without a guild are rolled up under guild 0.
+CREATE TABLE daily_status (
+    day BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    status SMALLINT NOT NULL,
+    secs BIGINT NOT NULL,
+    PRIMARY KEY (day, guild_id, user_id, status)
+);
+
+CREATE TABLE daily_activity (
+    day BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    activity_id INTEGER NOT NULL REFERENCES activity_names (id),
+    secs BIGINT NOT NULL,
+    PRIMARY KEY (day, guild_id, user_id, activity_id)
+);
+
+-- A single row once the rollups are built, the time up to which they are complete
+CREATE TABLE rollup_state (
+    id INTEGER PRIMARY KEY NOT NULL,
+    sealed_until BIGINT NOT NULL
+);
@@ -0,0 +1,29 @@
+CREATE TABLE watches_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    subscriber_id BIGINT NOT NULL,
+    target_id BIGINT NOT NULL,
+    activity TEXT,
+    status TEXT,
+    channel_id BIGINT,
+    last_notified BIGINT
+);
+
+INSERT INTO watches_old (id, guild_id, subscriber_id, target_id, activity, status, channel_id,
+    last_notified)
+SELECT id, guild_id, subscriber_id, target_id, activity,
+    CASE status
+        WHEN 1 THEN 'online'
+        WHEN 2 THEN 'idle'
+        WHEN 3 THEN 'dnd'
+        WHEN 4 THEN 'offline'
+        WHEN 5 THEN 'invisible'
+        WHEN 0 THEN 'unknown'
+    END,
+    channel_id, last_notified
+FROM watches;
+
+DROP TABLE watches;
+ALTER TABLE watches_old RENAME TO watches;
+
+CREATE INDEX watches_target_id ON watches (target_id);
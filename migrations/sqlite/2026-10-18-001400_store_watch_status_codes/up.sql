@@ -0,0 +1,31 @@
+-- Watched statuses become the codes logs use: 1 online, 2 idle, 3 dnd, 4 offline, 5 invisible
+CREATE TABLE watches_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    subscriber_id BIGINT NOT NULL,
+    target_id BIGINT NOT NULL,
+    activity TEXT,
+    status SMALLINT,
+    channel_id BIGINT,
+    last_notified BIGINT
+);
+
+INSERT INTO watches_new (id, guild_id, subscriber_id, target_id, activity, status, channel_id,
+    last_notified)
+SELECT id, guild_id, subscriber_id, target_id, activity,
+    CASE
+        WHEN status IS NULL THEN NULL
+        WHEN status = 'online' THEN 1
+        WHEN status = 'idle' THEN 2
+        WHEN status = 'dnd' THEN 3
+        WHEN status = 'offline' THEN 4
+        WHEN status = 'invisible' THEN 5
+        ELSE 0
+    END,
+    channel_id, last_notified
+FROM watches;
+
+DROP TABLE watches;
+ALTER TABLE watches_new RENAME TO watches;
+
+CREATE INDEX watches_target_id ON watches (target_id);
@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serenity::all::{ChannelId, Context, CreateMessage, UserId};
+
+use crate::aliases;
+use crate::sessions::{format_duration, load_sessions, unix_now, Session};
+use crate::storage::{
+    add_alert_history, get_alert_rules, get_last_alert_fired, AlertRule, LogQuery, LogStore,
+    NewAlertHistory, SharedLogStore, StorageError,
+};
+
+const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Whether `session` is what `rule` looks for, `activity` being the canonical name of the
+/// rule's activity.
+fn matches(rule: &AlertRule, activity: Option<&str>, session: &Session) -> bool {
+    if let Some(activity) = activity {
+        if !session.activity.eq_ignore_ascii_case(activity) {
+            return false;
+        }
+    }
+    if let Some(status) = &rule.status {
+        if session.status.as_str() != status {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn describe(rule: &AlertRule) -> String {
+    let who = match rule.target_id {
+        Some(target) => format!("<@{}>", target),
+        None => "anyone".to_string(),
+    };
+    let what = match (&rule.activity, &rule.status) {
+        (Some(activity), Some(status)) => format!("plays {} while {}", activity, status),
+        (Some(activity), None) => format!("plays {}", activity),
+        (None, Some(status)) => format!("is {}", status),
+        (None, None) => "does anything".to_string(),
+    };
+    format!(
+        "when {} {} for more than {}",
+        who,
+        what,
+        format_duration(rule.min_secs)
+    )
+}
+
+/// Finds, for every user, the matching streak of back-to-back sessions that is still going
+/// on at `now` and returns the users with the start of their streak.
+fn ongoing_streaks(rule: &AlertRule, sessions: &[Session], now: i64) -> Vec<(i64, i64)> {
+    let activity = rule.activity.as_deref().map(aliases::canonical);
+    let mut streaks = Vec::new();
+    let mut index = sessions.len();
+    while index > 0 {
+        let user = sessions[index - 1].user_id;
+        let mut start: Option<i64> = None;
+        let mut expected_end = now;
+        let mut broken = false;
+        while index > 0 && sessions[index - 1].user_id == user {
+            let session = &sessions[index - 1];
+            index -= 1;
+            if broken {
+                continue;
+            }
+            if session.end != expected_end || !matches(rule, activity.as_deref(), session) {
+                broken = true;
+                continue;
+            }
+            start = Some(session.start);
+            expected_end = session.start;
+        }
+        if let Some(start) = start {
+            streaks.push((user, start));
+        }
+    }
+    streaks
+}
+
+/// Start of the ongoing streak of `user` matching `rule`, looking back as far as `since`.
+fn streak_start(
+    store: &dyn LogStore,
+    rule: &AlertRule,
+    user: i64,
+    since: i64,
+    now: i64,
+) -> Result<Option<i64>, StorageError> {
+    let query = LogQuery::new().guild(Some(rule.guild_id)).user(user);
+    let sessions = load_sessions(store, since, now, query)?;
+    Ok(ongoing_streaks(rule, &sessions, now)
+        .first()
+        .map(|(_, start)| *start))
+}
+
+/// Periodically checks every rule against the sessions derived from the recorded presences.
+/// A rule fires once per streak, it can fire again for a user after their streak ended.
+pub fn spawn(ctx: Context, store: SharedLogStore) {
+    tokio::spawn(async move {
+        let mut fired: HashSet<(i32, i64)> = HashSet::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            evaluate(&ctx, &*store, &mut fired).await;
+        }
+    });
+}
+
+async fn evaluate(ctx: &Context, store: &dyn LogStore, fired: &mut HashSet<(i32, i64)>) {
+    let rules = match get_alert_rules(None) {
+        Ok(rules) => rules,
+        Err(err) => {
+            println!("Error while loading alert rules: {}", err);
+            return;
+        }
+    };
+
+    let now = unix_now();
+    let mut ongoing: HashSet<(i32, i64)> = HashSet::new();
+    for rule in rules {
+        // Twice the threshold, so a streak that already fired is still recognized as one
+        let from = now.saturating_sub(rule.min_secs.saturating_mul(2));
+        let mut query = LogQuery::new().guild(Some(rule.guild_id));
+        if let Some(target) = rule.target_id {
+            query = query.user(target);
+        }
+        let sessions = match load_sessions(store, from, now, query) {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                println!(
+                    "Error while loading sessions for alert #{}: {}",
+                    rule.id, err
+                );
+                continue;
+            }
+        };
+
+        for (user, start) in ongoing_streaks(&rule, &sessions, now) {
+            if now - start < rule.min_secs {
+                continue;
+            }
+            let key = (rule.id, user);
+            ongoing.insert(key);
+            if fired.contains(&key) {
+                continue;
+            }
+            // After a restart the history tells which streaks were already reported. A streak
+            // covering the whole window may have begun before it, so its start is looked up
+            // again as far back as the last alert.
+            if let Ok(Some(last)) = get_last_alert_fired(rule.id, user) {
+                let start = match start == from && last < from {
+                    true => match streak_start(store, &rule, user, last, now) {
+                        Ok(earlier) => earlier.unwrap_or(start),
+                        Err(err) => {
+                            println!(
+                                "Error while loading the streak of alert #{}: {}",
+                                rule.id, err
+                            );
+                            continue;
+                        }
+                    },
+                    false => start,
+                };
+                if last >= start {
+                    fired.insert(key);
+                    continue;
+                }
+            }
+            if fire(ctx, &rule, user, start, now).await {
+                fired.insert(key);
+            }
+        }
+    }
+    fired.retain(|key| ongoing.contains(key));
+}
+
+async fn fire(ctx: &Context, rule: &AlertRule, user: i64, start: i64, now: i64) -> bool {
+    let content = format!(
+        "Alert #{}: <@{}> has been going for {} ({})",
+        rule.id,
+        user,
+        format_duration(now - start),
+        describe(rule)
+    );
+    let sent = match rule.channel_id {
+        Some(channel) => ChannelId::new(channel as u64)
+            .send_message(
+                &ctx.http,
+                CreateMessage::new().content(format!("<@{}> {}", rule.creator_id, content)),
+            )
+            .await
+            .map(|_| ()),
+        None => UserId::new(rule.creator_id as u64)
+            .direct_message(&ctx.http, CreateMessage::new().content(content))
+            .await
+            .map(|_| ()),
+    };
+    if let Err(err) = sent {
+        println!("Cannot send alert #{}: {}", rule.id, err);
+        return false;
+    }
+
+    if let Err(err) = add_alert_history(NewAlertHistory {
+        rule_id: rule.id,
+        guild_id: rule.guild_id,
+        user_id: user,
+        streak_start: start,
+        fired_at: now,
+    }) {
+        println!("Error while recording alert #{}: {}", rule.id, err);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ongoing_streaks;
+    use crate::sessions::Session;
+    use crate::storage::AlertRule;
+    use crate::storage::Status::{self, Idle, Online};
+
+    fn session(user_id: i64, status: Status, start: i64, end: i64) -> Session {
+        Session {
+            user_id,
+            status,
+            activity: String::new(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn streaks_last_back_to_back_until_now() {
+        let rule = AlertRule {
+            id: 1,
+            guild_id: 5,
+            creator_id: 1,
+            target_id: None,
+            activity: None,
+            status: Some("online".to_string()),
+            min_secs: 60,
+            channel_id: None,
+            created_at: 0,
+        };
+        let sessions = [
+            session(1, Online, 0, 100),
+            session(1, Online, 100, 200),
+            session(2, Online, 0, 100),
+            session(2, Idle, 100, 150),
+            session(2, Online, 150, 200),
+            session(3, Online, 0, 100),
+            session(3, Online, 101, 200),
+            session(4, Online, 0, 199),
+        ];
+        let mut streaks = ongoing_streaks(&rule, &sessions, 200);
+        streaks.sort();
+        assert_eq!(streaks, vec![(1, 0), (2, 150), (3, 101)]);
+    }
+}
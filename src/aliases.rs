@@ -0,0 +1,86 @@
+//! Alias rules turning the activity names Discord reports into canonical ones, so that
+//! "CS2" and "Counter-Strike 2" count as the same game. `activity_names` keeps the raw name
+//! of every activity next to its canonical one, which is refreshed whenever rules change.
+
+use std::sync::Mutex;
+
+use lazy_regex::Regex;
+
+use crate::storage::{get_activity_aliases, ActivityAlias};
+
+enum Matcher {
+    Exact(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    /// Exact patterns ignore case, regexes match anywhere in the name unless anchored.
+    fn new(pattern: &str, is_regex: bool) -> Result<Self, String> {
+        if !is_regex {
+            return Ok(Matcher::Exact(pattern.to_string()));
+        }
+        Regex::new(pattern)
+            .map(Matcher::Regex)
+            .map_err(|err| format!("Invalid regex: {}", err))
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Matcher::Exact(pattern) => pattern.eq_ignore_ascii_case(name),
+            Matcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+/// Checks a pattern before it is saved as a rule.
+pub fn validate(pattern: &str, is_regex: bool) -> Result<(), String> {
+    Matcher::new(pattern, is_regex).map(|_| ())
+}
+
+/// Compiled alias rules, the oldest rule matching a name decides its canonical name.
+pub struct AliasRules {
+    rules: Vec<(Matcher, String)>,
+}
+
+impl AliasRules {
+    pub fn new(aliases: &[ActivityAlias]) -> Self {
+        let rules = aliases
+            .iter()
+            .filter_map(|alias| {
+                let matcher = Matcher::new(&alias.pattern, alias.is_regex).ok()?;
+                Some((matcher, alias.canonical.clone()))
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The canonical name of `raw`, an empty one meaning it isn't an activity at all.
+    pub fn canonical(&self, raw: &str) -> String {
+        self.rules
+            .iter()
+            .find(|(matcher, _)| matcher.matches(raw))
+            .map(|(_, canonical)| canonical.clone())
+            .unwrap_or_else(|| raw.to_string())
+    }
+}
+
+/// Rules for live presences, loaded on first use and again after `reload`.
+static CACHE: Mutex<Option<AliasRules>> = Mutex::new(None);
+
+pub fn canonical(raw: &str) -> String {
+    let mut cache = CACHE.lock().unwrap();
+    if cache.is_none() {
+        match get_activity_aliases() {
+            Ok(aliases) => *cache = Some(AliasRules::new(&aliases)),
+            Err(err) => {
+                println!("Error while loading activity aliases: {}", err);
+                return raw.to_string();
+            }
+        }
+    }
+    cache.as_ref().unwrap().canonical(raw)
+}
+
+pub fn reload() {
+    *CACHE.lock().unwrap() = None;
+}
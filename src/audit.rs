@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use serenity::all::{CommandInteraction, ResolvedOption, ResolvedTarget, ResolvedValue};
+
+use crate::sessions::unix_now;
+use crate::storage::{add_audit, NewAudit};
+
+/// What became of an invocation.
+pub enum Outcome {
+    Ok,
+    /// The invoker is not on the allow list.
+    Denied,
+    /// The invoker is over the rate limit and can retry after the given time.
+    RateLimited(Duration),
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Ok => "ok",
+            Outcome::Denied => "denied",
+            Outcome::RateLimited(_) => "rate_limited",
+        }
+    }
+}
+
+/// Flattens options into `name=value` pairs, subcommands showing up as their bare name.
+/// Returns the first user found in the options, the target of the lookup.
+fn describe_options(options: &[ResolvedOption], parts: &mut Vec<String>) -> Option<i64> {
+    let mut target: Option<i64> = None;
+    for option in options {
+        let value = match &option.value {
+            ResolvedValue::SubCommand(options) | ResolvedValue::SubCommandGroup(options) => {
+                parts.push(option.name.to_string());
+                let nested = describe_options(options, parts);
+                target = target.or(nested);
+                continue;
+            }
+            ResolvedValue::User(user, _) => {
+                target = target.or(Some(user.id.into()));
+                format!("<@{}>", user.id)
+            }
+            ResolvedValue::Channel(channel) => format!("<#{}>", channel.id),
+            ResolvedValue::Role(role) => format!("<@&{}>", role.id),
+            ResolvedValue::String(value) => format!("{:?}", value),
+            ResolvedValue::Integer(value) => value.to_string(),
+            ResolvedValue::Number(value) => value.to_string(),
+            ResolvedValue::Boolean(value) => value.to_string(),
+            ResolvedValue::Attachment(attachment) => attachment.filename.clone(),
+            _ => "?".to_string(),
+        };
+        parts.push(format!("{}={}", option.name, value));
+    }
+    target
+}
+
+/// Stores who invoked which command with which options and whether it was served. Every
+/// invocation is recorded, including the ones turned away and the ones sent in DMs.
+pub fn record(command: &CommandInteraction, outcome: &Outcome) {
+    let mut parts: Vec<String> = Vec::new();
+    let mut target = describe_options(&command.data.options(), &mut parts);
+    if let Some(ResolvedTarget::User(user, _)) = command.data.target() {
+        target = Some(user.id.into());
+        parts.push(format!("target=<@{}>", user.id));
+    }
+
+    if let Err(err) = add_audit(NewAudit {
+        guild_id: command.guild_id.map(i64::from),
+        invoker_id: command.user.id.into(),
+        command: command.data.name.clone(),
+        options: parts.join(" "),
+        target_id: target,
+        unix_time: unix_now(),
+        outcome: outcome.as_str().to_string(),
+    }) {
+        println!("Error while recording a command in the audit log: {}", err);
+    }
+}
@@ -0,0 +1,201 @@
+//! Snapshots of the SQLite database taken with `VACUUM INTO`, which writes a consistent and
+//! compacted copy of the live database. Configured in the environment: `BACKUP_DIR` is where
+//! snapshots are written (`backups` by default), `BACKUP_KEEP` how many of them are kept (7
+//! by default) and `BACKUP_SCHEDULE` a cron expression taking them automatically, such as
+//! `0 4 * * *`. PostgreSQL deployments are backed up with `pg_dump` instead.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDateTime, Utc};
+use cron::Schedule;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel_migrations::MigrationHarness;
+
+use crate::migrate::{check_schema, MIGRATIONS};
+use crate::scheduler::parse_schedule;
+
+const PREFIX: &str = "backup-";
+const EXTENSION: &str = "db";
+/// Timestamps in snapshot names, in UTC so they sort the way they were taken. Milliseconds
+/// keep two backups taken within the same second apart.
+const TIME_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+pub fn backup_dir() -> PathBuf {
+    env::var("BACKUP_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("backups"))
+}
+
+/// Number of snapshots kept, at least the one just taken.
+fn keep() -> usize {
+    env::var("BACKUP_KEEP")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(7)
+        .max(1)
+}
+
+/// The schedule of automatic backups, `None` when they are off.
+pub fn schedule() -> Option<Schedule> {
+    let expression = env::var("BACKUP_SCHEDULE").ok()?;
+    match parse_schedule(&expression) {
+        Ok(schedule) => Some(schedule),
+        Err(err) => {
+            println!("Invalid BACKUP_SCHEDULE, backups are off: {}", err);
+            None
+        }
+    }
+}
+
+fn database_path() -> Result<PathBuf, String> {
+    dotenv::dotenv().ok();
+    env::var("DATABASE_URL")
+        .map(PathBuf::from)
+        .map_err(|_| "DATABASE_URL must be set".to_string())
+}
+
+fn open(path: &Path) -> Result<SqliteConnection, String> {
+    let url = path
+        .to_str()
+        .ok_or(format!("{} is not a valid path", path.display()))?;
+    SqliteConnection::establish(url).map_err(|err| format!("Cannot open {}: {}", url, err))
+}
+
+/// Writes a copy of the database open on `conn` to `target`, which must not exist yet.
+fn vacuum_into(conn: &mut SqliteConnection, target: &Path) -> Result<(), String> {
+    let target = target
+        .to_str()
+        .ok_or(format!("{} is not a valid path", target.display()))?;
+    diesel::sql_query(format!("VACUUM INTO '{}'", target.replace('\'', "''")))
+        .execute(conn)
+        .map(|_| ())
+        .map_err(|err| format!("Cannot write {}: {}", target, err))
+}
+
+#[derive(QueryableByName)]
+struct IntegrityCheck {
+    #[diesel(sql_type = Text)]
+    integrity_check: String,
+}
+
+/// Runs SQLite's integrity check over the whole database file at `path`.
+pub fn check_integrity(path: &Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Err(format!("{} doesn't exist", path.display()));
+    }
+    let problems: Vec<IntegrityCheck> = diesel::sql_query("PRAGMA integrity_check")
+        .load(&mut open(path)?)
+        .map_err(|err| format!("Cannot check {}: {}", path.display(), err))?;
+    match problems.as_slice() {
+        [check] if check.integrity_check == "ok" => Ok(()),
+        _ => Err(format!(
+            "{} failed the integrity check: {}",
+            path.display(),
+            problems
+                .iter()
+                .map(|check| check.integrity_check.as_str())
+                .collect::<Vec<&str>>()
+                .join("; ")
+        )),
+    }
+}
+
+/// The snapshots in `dir` with the unix time they were taken, oldest first.
+fn snapshots(dir: &Path) -> Result<Vec<(i64, PathBuf)>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("Cannot read {}: {}", dir.display(), err)),
+    };
+    let mut snapshots: Vec<(i64, PathBuf)> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let time = name.strip_prefix(PREFIX)?.strip_suffix(EXTENSION)?;
+            let time = NaiveDateTime::parse_from_str(time.strip_suffix('.')?, TIME_FORMAT).ok()?;
+            Some((time.and_utc().timestamp(), path))
+        })
+        .collect();
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// Unix time of the newest snapshot in `dir`.
+pub fn latest(dir: &Path) -> Result<Option<i64>, String> {
+    Ok(snapshots(dir)?.last().map(|(time, _)| *time))
+}
+
+/// Snapshots the database into `dir`, checks the snapshot and deletes the oldest ones beyond
+/// `BACKUP_KEEP`. Returns the path of the new snapshot.
+pub fn backup(dir: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|err| format!("Cannot create {}: {}", dir.display(), err))?;
+    let path = dir.join(format!(
+        "{}{}.{}",
+        PREFIX,
+        Utc::now().format(TIME_FORMAT),
+        EXTENSION
+    ));
+    vacuum_into(&mut open(&database_path()?)?, &path)?;
+    if let Err(err) = check_integrity(&path) {
+        fs::remove_file(&path).ok();
+        return Err(err);
+    }
+
+    let snapshots = snapshots(dir)?;
+    let outdated = snapshots.len().saturating_sub(keep());
+    for (_, old) in &snapshots[..outdated] {
+        fs::remove_file(old).map_err(|err| format!("Cannot delete {}: {}", old.display(), err))?;
+    }
+    Ok(path)
+}
+
+/// Replaces the database with the snapshot at `path`. The snapshot is checked and brought up
+/// to date on a copy first, and the current database is backed up into `dir` before it is
+/// replaced, that backup being returned. The bot must not be running meanwhile.
+pub fn restore(path: &Path, dir: &Path) -> Result<Option<PathBuf>, String> {
+    check_integrity(path)?;
+    let target = database_path()?;
+    let staged = target.with_extension("restoring");
+    if staged.exists() {
+        fs::remove_file(&staged)
+            .map_err(|err| format!("Cannot delete {}: {}", staged.display(), err))?;
+    }
+    vacuum_into(&mut open(path)?, &staged)?;
+    let prepared = open(&staged).and_then(|mut conn| {
+        conn.run_pending_migrations(MIGRATIONS)
+            .map_err(|err| format!("Cannot update {}: {}", path.display(), err))?;
+        check_schema(&mut conn).map_err(|err| err.to_string())
+    });
+    if let Err(err) = prepared {
+        fs::remove_file(&staged).ok();
+        return Err(err);
+    }
+
+    let previous = match target.exists() {
+        true => Some(backup(dir)?),
+        false => None,
+    };
+    fs::rename(&staged, &target).map_err(|err| {
+        format!(
+            "Cannot move {} to {}: {}",
+            staged.display(),
+            target.display(),
+            err
+        )
+    })?;
+    Ok(previous)
+}
+
+/// `path` with the size of the file, for messages.
+pub fn describe(path: &Path) -> String {
+    let bytes = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    let size = match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    };
+    format!("{} ({})", path.display(), size)
+}
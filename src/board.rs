@@ -0,0 +1,315 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serenity::all::{
+    Activity, ActivityType, ChannelId, Context, CreateEmbed, CreateEmbedFooter, CreateMessage,
+    EditMessage, GuildId, MessageId, OnlineStatus, UserId,
+};
+
+use crate::aliases;
+use crate::sessions::unix_now;
+use crate::storage::{Board, Distinct, LogQuery, LogStore, SettingsStore, SharedSettingsStore};
+
+/// Discord refuses embeds with more than 25 fields or fields longer than 1024 characters.
+const MAX_FIELDS: usize = 25;
+const MAX_FIELD_LEN: usize = 1024;
+/// Discord also refuses embeds whose title, fields and footer add up to more than 6000.
+const MAX_EMBED_LEN: usize = 6000;
+/// Room kept for the line counting the games that didn't fit.
+const OVERFLOW_RESERVE: usize = 32;
+const TITLE: &str = "Now playing";
+const FOOTER: &str = "Last updated";
+/// Error code returned by Discord when the message we try to edit no longer exists.
+const UNKNOWN_MESSAGE: isize = 10008;
+/// How far back the logs are searched to rebuild the board after a restart.
+const SEED_WINDOW_SECS: i64 = 6 * 60 * 60;
+
+pub type SharedNowPlaying = Arc<Mutex<NowPlaying>>;
+
+/// Who is playing what right now, per guild, together with the set of guilds whose
+/// board message is out of date.
+#[derive(Default)]
+pub struct NowPlaying {
+    players: HashMap<GuildId, HashMap<UserId, String>>,
+    dirty: HashSet<GuildId>,
+}
+
+impl NowPlaying {
+    pub fn update(&mut self, guild: GuildId, user: UserId, game: Option<String>) {
+        let players = self.players.entry(guild).or_default();
+        let changed = match game {
+            Some(game) => players.insert(user, game.clone()).as_ref() != Some(&game),
+            None => players.remove(&user).is_some(),
+        };
+        if changed {
+            self.dirty.insert(guild);
+        }
+    }
+
+    pub fn mark_dirty(&mut self, guild: GuildId) {
+        self.dirty.insert(guild);
+    }
+
+    fn take_dirty(&mut self) -> HashSet<GuildId> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn players(&self, guild: GuildId) -> HashMap<UserId, String> {
+        self.players.get(&guild).cloned().unwrap_or_default()
+    }
+}
+
+/// Picks the game out of a presence, ignoring custom statuses, music and the like.
+pub fn playing_activity(status: OnlineStatus, activities: &[Activity]) -> Option<String> {
+    if status == OnlineStatus::Offline || status == OnlineStatus::Invisible {
+        return None;
+    }
+    activities
+        .iter()
+        .find(|activity| {
+            activity.kind == ActivityType::Playing || activity.kind == ActivityType::Competing
+        })
+        .map(|activity| aliases::canonical(&activity.name))
+        .filter(|name| !name.is_empty())
+}
+
+fn update_interval() -> Duration {
+    let secs = env::var("BOARD_UPDATE_INTERVAL")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(30)
+        .max(5);
+    Duration::from_secs(secs)
+}
+
+/// Rebuilds the in-memory state from the latest logs so the board survives restarts.
+/// Logs don't record the activity kind, so this is a best effort until presences arrive.
+pub fn seed(state: &SharedNowPlaying, store: &dyn LogStore, settings: &dyn SettingsStore) {
+    let boards = match settings.get_boards() {
+        Ok(boards) => boards,
+        Err(err) => {
+            println!("Error while loading boards: {}", err);
+            return;
+        }
+    };
+
+    let since = unix_now() - SEED_WINDOW_SECS;
+    for board in boards {
+        let query = LogQuery::new()
+            .since(since)
+            .guild(Some(board.guild_id))
+            .distinct(Distinct::User);
+        let latest = match store.query(&query) {
+            Ok(latest) => latest,
+            Err(err) => {
+                println!("Error while seeding the board: {}", err);
+                continue;
+            }
+        };
+
+        let mut state = state.lock().unwrap();
+        let guild = GuildId::new(board.guild_id as u64);
+        for log in &latest {
+            if !log.status.is_offline() && !log.activity.is_empty() {
+                state.update(
+                    guild,
+                    UserId::new(log.user_id as u64),
+                    Some(log.activity.clone()),
+                );
+            }
+        }
+        state.mark_dirty(guild);
+    }
+}
+
+/// Periodically edits every board that changed since the last tick. Edits are batched per
+/// interval, so a burst of presence updates results in a single request per board.
+pub fn spawn(ctx: Context, state: SharedNowPlaying, settings: SharedSettingsStore) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(update_interval());
+        loop {
+            interval.tick().await;
+            let dirty = state.lock().unwrap().take_dirty();
+            let boards = match settings.get_boards() {
+                Ok(boards) => boards,
+                Err(err) => {
+                    println!("Error while loading boards: {}", err);
+                    state.lock().unwrap().dirty.extend(dirty);
+                    continue;
+                }
+            };
+
+            for board in boards {
+                let guild = GuildId::new(board.guild_id as u64);
+                if board.message_id.is_some() && !dirty.contains(&guild) {
+                    continue;
+                }
+                let players = state.lock().unwrap().players(guild);
+                if !refresh(&ctx, &*settings, &board, &players).await {
+                    state.lock().unwrap().mark_dirty(guild);
+                }
+            }
+        }
+    });
+}
+
+/// Edits the board message, posting and pinning a new one when it is missing.
+/// Returns false when the update should be retried on the next tick.
+async fn refresh(
+    ctx: &Context,
+    settings: &dyn SettingsStore,
+    board: &Board,
+    players: &HashMap<UserId, String>,
+) -> bool {
+    let channel = ChannelId::new(board.channel_id as u64);
+    let embed = render(board, players);
+
+    if let Some(message_id) = board.message_id {
+        let message = MessageId::new(message_id as u64);
+        match channel
+            .edit_message(&ctx.http, message, EditMessage::new().embed(embed.clone()))
+            .await
+        {
+            Ok(_) => return true,
+            Err(serenity::Error::Http(serenity::http::HttpError::UnsuccessfulRequest(err)))
+                if err.error.code == UNKNOWN_MESSAGE =>
+            {
+                println!(
+                    "Board message in {} was deleted, posting a new one",
+                    channel
+                );
+            }
+            Err(err) => {
+                println!("Cannot edit the board message: {}", err);
+                return false;
+            }
+        }
+    }
+
+    match channel
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await
+    {
+        Ok(message) => {
+            if let Err(err) = message.pin(&ctx.http).await {
+                println!("Cannot pin the board message: {}", err);
+            }
+            if let Err(err) = settings.set_board_message(board.guild_id, Some(message.id.into())) {
+                println!("Error while saving the board message: {}", err);
+            }
+            true
+        }
+        Err(err) => {
+            println!("Cannot post the board message: {}", err);
+            false
+        }
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn render(board: &Board, players: &HashMap<UserId, String>) -> CreateEmbed {
+    let include = split_list(&board.include_activities);
+    let exclude = split_list(&board.exclude_activities);
+
+    let mut games: HashMap<&str, Vec<UserId>> = HashMap::new();
+    for (user, game) in players {
+        let name = game.to_lowercase();
+        if (!include.is_empty() && !include.contains(&name)) || exclude.contains(&name) {
+            continue;
+        }
+        games.entry(game.as_str()).or_default().push(*user);
+    }
+
+    let mut games: Vec<(&str, Vec<UserId>)> = games.into_iter().collect();
+    games.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+
+    let mut embed = CreateEmbed::new()
+        .title(TITLE)
+        .footer(CreateEmbedFooter::new(FOOTER))
+        .timestamp(serenity::model::Timestamp::now());
+    if games.is_empty() {
+        return embed.description("Nobody is playing anything right now");
+    }
+    let count = games.len();
+    let mut total = TITLE.len() + FOOTER.len();
+    for (index, (game, mut users)) in games.into_iter().enumerate() {
+        users.sort();
+        let mut value = String::new();
+        for (shown, user) in users.iter().enumerate() {
+            let mention = format!("<@{}>", user);
+            if value.len() + mention.len() + 20 > MAX_FIELD_LEN {
+                value += &format!(" and {} more", users.len() - shown);
+                break;
+            }
+            if !value.is_empty() {
+                value += ", ";
+            }
+            value += &mention;
+        }
+        let name = format!("{} ({})", game, users.len());
+        let more = count - index;
+        if index == MAX_FIELDS
+            || total + name.len() + value.len() + OVERFLOW_RESERVE > MAX_EMBED_LEN
+        {
+            let games = if more == 1 { "game" } else { "games" };
+            return embed.description(format!("…and {} more {}", more, games));
+        }
+        total += name.len() + value.len();
+        embed = embed.field(name, value, false);
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serenity::all::UserId;
+
+    use super::{render, MAX_EMBED_LEN};
+    use crate::storage::Board;
+
+    #[test]
+    fn collapses_the_games_that_do_not_fit_in_the_embed() {
+        let board = Board {
+            guild_id: 5,
+            channel_id: 6,
+            message_id: None,
+            include_activities: String::new(),
+            exclude_activities: String::new(),
+        };
+        let mut players = HashMap::new();
+        for game in 0..20 {
+            for player in 0..40u64 {
+                let user = UserId::new(100_000_000_000_000_000 + game * 100 + player);
+                players.insert(user, format!("Game {:02}", game));
+            }
+        }
+
+        let embed = serde_json::to_value(render(&board, &players)).unwrap();
+        let fields = embed["fields"].as_array().unwrap();
+        let total = "Now playing".len()
+            + "Last updated".len()
+            + embed["description"].as_str().unwrap().len()
+            + fields
+                .iter()
+                .map(|field| {
+                    field["name"].as_str().unwrap().len() + field["value"].as_str().unwrap().len()
+                })
+                .sum::<usize>();
+        assert!(total <= MAX_EMBED_LEN);
+        assert_eq!(fields[0]["name"], "Game 00 (40)");
+        assert_eq!(
+            embed["description"],
+            format!("…and {} more games", 20 - fields.len())
+        );
+    }
+}
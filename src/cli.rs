@@ -0,0 +1,281 @@
+//! Maintenance subcommands, run instead of the bot when the binary gets arguments:
+//!
+//! ```text
+//! discord-status-monitor export [--user ID] [--activity NAME] [--status STATUS]
+//!     [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--format csv|json|ndjson] [--output FILE]
+//! discord-status-monitor import FILE [--format csv|json|ndjson] [--map FIELD=COLUMN,...]
+//!     [--dry-run]
+//! discord-status-monitor migrate [status|run|rollback] [--steps N]
+//! discord-status-monitor migrate-data --from FILE
+//! discord-status-monitor rebuild-rollups
+//! discord-status-monitor backup [--dir DIR]
+//! discord-status-monitor restore FILE [--dir DIR]
+//! ```
+//!
+//! Every command except `migrate` and `restore` applies the pending migrations first.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+
+use crate::export::{export_logs, parse_date_range, ExportFormat};
+use crate::import::{detect_format, import_logs, read_rows, Mapping};
+use crate::migrate;
+use crate::sessions::{configured_timezone, unix_now};
+use crate::storage::{rebuild_rollups, DatabaseStore, LogQuery, Status};
+
+const USAGE: &str = "Usage: discord-status-monitor <command> [options]
+
+Commands:
+  export    Write logs to a gzip compressed CSV, JSON or NDJSON file
+            --user ID  --activity NAME  --status online|idle|dnd|offline|invisible
+            --from YYYY-MM-DD  --to YYYY-MM-DD  --format csv|json|ndjson  --output FILE
+  import    Backfill logs from a CSV, JSON or NDJSON file, optionally gzip compressed
+            FILE  --format csv|json|ndjson  --map user_id=COLUMN,status=COLUMN,...
+            --dry-run  Only report what would be imported
+  migrate   Manage the database schema
+            status    List the migrations and whether they were applied (default)
+            run       Apply the pending migrations
+            rollback  Revert the last applied migration, --steps N to revert more
+  migrate-data  Copy a SQLite database into PostgreSQL, needs the postgres feature
+            --from FILE
+  rebuild-rollups  Recompute the daily statistics rollups from the logs
+  backup    Snapshot the SQLite database and delete the oldest snapshots beyond BACKUP_KEEP
+            --dir DIR  Where to write the snapshot, BACKUP_DIR by default
+  restore   Replace the SQLite database with a snapshot, stop the bot first
+            FILE  --dir DIR  Where to back the current database up first
+
+Run without a command to start the bot.";
+
+/// Runs the subcommand named by the first argument and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => ("help", args),
+    };
+    if !matches!(
+        command,
+        "migrate" | "migrate-data" | "restore" | "help" | "--help" | "-h"
+    ) {
+        if let Err(err) = migrate::setup() {
+            eprintln!("Cannot prepare the database: {}", err);
+            return 1;
+        }
+    }
+    let result = match command {
+        "migrate" => parse_flags(rest, &[]).and_then(|args| migration(&args)),
+        "migrate-data" => parse_flags(rest, &[]).and_then(|args| migrate_data(&args)),
+        "export" => parse_flags(rest, &[]).and_then(|args| export(&args)),
+        "import" => parse_flags(rest, &["dry-run"]).and_then(|args| import(&args)),
+        "rebuild-rollups" => parse_flags(rest, &[]).and_then(|args| rebuild(&args)),
+        "backup" => parse_flags(rest, &[]).and_then(|args| backup(&args)),
+        "restore" => parse_flags(rest, &[]).and_then(|args| restore(&args)),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        _ => Err(format!("Unknown command {}\n\n{}", command, USAGE)),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+#[derive(Default)]
+struct Args {
+    positional: Vec<String>,
+    flags: HashMap<String, String>,
+    switches: Vec<String>,
+}
+
+impl Args {
+    fn flag(&self, name: &str) -> Option<&String> {
+        self.flags.get(name)
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|switch| switch == name)
+    }
+}
+
+/// Parses `--name value` pairs, the `switches` that take no value and positional arguments.
+fn parse_flags(args: &[String], switches: &[&str]) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(name) = arg.strip_prefix("--") else {
+            parsed.positional.push(arg.clone());
+            continue;
+        };
+        if switches.contains(&name) {
+            parsed.switches.push(name.to_string());
+            continue;
+        }
+        let Some(value) = args.next() else {
+            return Err(format!("Missing a value for --{}", name));
+        };
+        parsed.flags.insert(name.to_string(), value.clone());
+    }
+    Ok(parsed)
+}
+
+fn export(args: &Args) -> Result<(), String> {
+    if let Some(arg) = args.positional.first() {
+        return Err(format!("Unexpected argument {}", arg));
+    }
+    let flags = &args.flags;
+    let format = match flags.get("format") {
+        Some(value) => ExportFormat::parse(value).ok_or(format!("Unknown format {}", value))?,
+        None => ExportFormat::Csv,
+    };
+    let user_id = match flags.get("user") {
+        Some(value) => Some(
+            value
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid user id {}", value))?,
+        ),
+        None => None,
+    };
+    let (from, to) = parse_date_range(
+        flags.get("from").map(String::as_str),
+        flags.get("to").map(String::as_str),
+        configured_timezone(),
+    )?;
+    let mut query = LogQuery::new().between(from, to);
+    if let Some(user_id) = user_id {
+        query = query.user(user_id);
+    }
+    if let Some(activity) = flags.get("activity") {
+        query = query.activity(activity);
+    }
+    if let Some(status) = flags.get("status") {
+        query = query.status(status.parse::<Status>()?);
+    }
+
+    let path = flags.get("output").cloned().unwrap_or(format.file_name());
+    let file = File::create(&path).map_err(|err| format!("Cannot create {}: {}", path, err))?;
+    let count = export_logs(&DatabaseStore, BufWriter::new(file), &query, format)?;
+    println!("Exported {} logs to {}", count, path);
+    Ok(())
+}
+
+fn import(args: &Args) -> Result<(), String> {
+    let [path] = args.positional.as_slice() else {
+        return Err(format!("Expected the file to import\n\n{}", USAGE));
+    };
+    let format = match args.flag("format") {
+        Some(value) => ExportFormat::parse(value).ok_or(format!("Unknown format {}", value))?,
+        None => detect_format(path).ok_or("Cannot tell the file format, pass --format")?,
+    };
+    let mapping = match args.flag("map") {
+        Some(value) => Mapping::parse(value)?,
+        None => Mapping::default(),
+    };
+
+    let rows = read_rows(path, format)?;
+    let report = import_logs(&DatabaseStore, &rows, &mapping, args.switch("dry-run"))?;
+    println!("{}", report);
+    Ok(())
+}
+
+fn rebuild(args: &Args) -> Result<(), String> {
+    if let Some(arg) = args.positional.first() {
+        return Err(format!("Unexpected argument {}", arg));
+    }
+    let (logs, rows) = rebuild_rollups(unix_now()).map_err(|err| err.to_string())?;
+    println!("Rolled up {} logs into {} daily rows", logs, rows);
+    Ok(())
+}
+
+fn migration(args: &Args) -> Result<(), String> {
+    match args.positional.first().map(String::as_str) {
+        None | Some("status") => {
+            for (name, applied) in migrate::status().map_err(|err| err.to_string())? {
+                let state = if applied { "applied" } else { "pending" };
+                println!("{:<8} {}", state, name);
+            }
+        }
+        Some("run") => {
+            migrate::setup().map_err(|err| err.to_string())?;
+            println!("The database is up to date");
+        }
+        Some("rollback") => {
+            let steps = match args.flag("steps") {
+                Some(value) => value
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid number of steps {}", value))?,
+                None => 1,
+            };
+            for version in migrate::rollback(steps).map_err(|err| err.to_string())? {
+                println!("Reverted migration {}", version);
+            }
+        }
+        Some(action) => return Err(format!("Unknown migrate action {}\n\n{}", action, USAGE)),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+fn migrate_data(args: &Args) -> Result<(), String> {
+    let Some(path) = args.flag("from") else {
+        return Err(format!("Expected --from FILE\n\n{}", USAGE));
+    };
+    for (table, count) in crate::migrate_data::copy_from_sqlite(path)? {
+        println!("{:<14} {} rows", table, count);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "postgres"))]
+fn migrate_data(_args: &Args) -> Result<(), String> {
+    Err("migrate-data copies into PostgreSQL, build with --features postgres".to_string())
+}
+
+#[cfg(not(feature = "postgres"))]
+fn backup_dir(args: &Args) -> std::path::PathBuf {
+    args.flag("dir")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(crate::backup::backup_dir)
+}
+
+#[cfg(not(feature = "postgres"))]
+fn backup(args: &Args) -> Result<(), String> {
+    if let Some(arg) = args.positional.first() {
+        return Err(format!("Unexpected argument {}", arg));
+    }
+    let path = crate::backup::backup(&backup_dir(args))?;
+    println!(
+        "Backed up the database to {}",
+        crate::backup::describe(&path)
+    );
+    Ok(())
+}
+
+#[cfg(not(feature = "postgres"))]
+fn restore(args: &Args) -> Result<(), String> {
+    let [path] = args.positional.as_slice() else {
+        return Err(format!("Expected the snapshot to restore\n\n{}", USAGE));
+    };
+    if let Some(previous) = crate::backup::restore(path.as_ref(), &backup_dir(args))? {
+        println!(
+            "Backed the replaced database up to {}",
+            crate::backup::describe(&previous)
+        );
+    }
+    println!("Restored the database from {}", path);
+    Ok(())
+}
+
+#[cfg(feature = "postgres")]
+fn backup(_args: &Args) -> Result<(), String> {
+    Err("backup only works with SQLite, back PostgreSQL up with pg_dump".to_string())
+}
+
+#[cfg(feature = "postgres")]
+fn restore(_args: &Args) -> Result<(), String> {
+    Err("restore only works with SQLite, restore PostgreSQL with pg_restore".to_string())
+}
@@ -0,0 +1,56 @@
+use serenity::all::{GuildId, Permissions};
+use serenity::builder::{CreateCommand, CreateCommandOption, EditInteractionResponse};
+use serenity::model::application::{CommandOptionType, ResolvedOption};
+
+use crate::storage::{SettingsStore, SharedSettingsStore};
+
+/// Returns the maintenance task to run once the reply is deferred, a backup of a large
+/// database takes longer than Discord waits for a reply.
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    settings: SharedSettingsStore,
+) -> impl FnOnce() -> EditInteractionResponse + Send + 'static {
+    let subcommand = options.first().map(|option| option.name.to_string());
+    move || {
+        let message = EditInteractionResponse::new();
+        if guild.is_none() {
+            return message.content("This command can only be used in a server");
+        }
+        match subcommand.as_deref() {
+            Some("backup") => message.content(backup(&*settings)),
+            _ => message.content("Unknown subcommand"),
+        }
+    }
+}
+
+#[cfg(not(feature = "postgres"))]
+fn backup(settings: &dyn SettingsStore) -> String {
+    match settings.backup() {
+        Ok(path) => format!(
+            "Backed up the database to {}",
+            crate::backup::describe(&path)
+        ),
+        Err(err) => {
+            println!("Error while backing up the database: {}", err);
+            "The backup failed, see the bot's logs".to_string()
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn backup(_settings: &dyn SettingsStore) -> String {
+    "Backups only work with SQLite, back PostgreSQL up with pg_dump".to_string()
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("admin")
+        .description("Maintain the bot")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "backup",
+            "Snapshot the database now",
+        ))
+}
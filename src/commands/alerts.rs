@@ -0,0 +1,224 @@
+use serenity::all::{GuildId, Permissions, UserId};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::alerts::describe;
+use crate::sessions::{parse_duration, unix_now};
+use crate::storage::{user_error, NewAlertRule, SettingsStore};
+
+const MAX_RULES: usize = 50;
+/// Streaks are looked for in the sessions of twice the duration, reloaded every minute.
+const MAX_DURATION_SECS: i64 = 30 * 24 * 60 * 60;
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    creator: UserId,
+    settings: &dyn SettingsStore,
+) -> String {
+    let Some(guild) = guild else {
+        return "This command can only be used in a server".to_string();
+    };
+
+    match options.first() {
+        Some(ResolvedOption {
+            name: "add",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let mut rule = NewAlertRule {
+                guild_id: guild.into(),
+                creator_id: creator.into(),
+                target_id: None,
+                activity: None,
+                status: None,
+                min_secs: 0,
+                channel_id: None,
+                created_at: unix_now(),
+            };
+            for option in options {
+                match (option.name, &option.value) {
+                    ("duration", ResolvedValue::String(value)) => {
+                        rule.min_secs = parse_duration(value).unwrap_or(0)
+                    }
+                    ("user", ResolvedValue::User(user, _)) => rule.target_id = Some(user.id.into()),
+                    ("activity", ResolvedValue::String(value)) => {
+                        rule.activity = Some(value.trim().to_string())
+                    }
+                    ("status", ResolvedValue::String(value)) => {
+                        rule.status = Some(value.to_string())
+                    }
+                    ("channel", ResolvedValue::Channel(channel)) => {
+                        rule.channel_id = Some(channel.id.into())
+                    }
+                    _ => {}
+                }
+            }
+            if rule.min_secs < 60 {
+                return "Please provide a duration of at least a minute, such as 90m or 4h"
+                    .to_string();
+            }
+            if rule.min_secs > MAX_DURATION_SECS {
+                return format!(
+                    "Please provide a duration of at most {} days",
+                    MAX_DURATION_SECS / (24 * 60 * 60)
+                );
+            }
+            if rule.activity.is_none() && rule.status.is_none() {
+                return "Please provide an activity, a status or both".to_string();
+            }
+            match settings.get_alert_rules(Some(rule.guild_id)) {
+                Ok(rules) if rules.len() >= MAX_RULES => {
+                    return format!("This server already has {} alert rules", MAX_RULES)
+                }
+                Ok(_) => {}
+                Err(err) => return user_error(err),
+            }
+            match settings.add_alert_rule(rule) {
+                Ok(()) => "The alert rule was added".to_string(),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption { name: "list", .. }) => {
+            match settings.get_alert_rules(Some(guild.into())) {
+                Ok(rules) if rules.is_empty() => "There are no alert rules".to_string(),
+                Ok(rules) => rules
+                    .iter()
+                    .map(|rule| {
+                        format!(
+                            "#{}: {}, {} (added by <@{}>)",
+                            rule.id,
+                            describe(rule),
+                            match rule.channel_id {
+                                Some(channel) => format!("post in <#{}>", channel),
+                                None => "send a DM".to_string(),
+                            },
+                            rule.creator_id
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption {
+            name: "remove",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let Some(ResolvedOption {
+                value: ResolvedValue::Integer(id),
+                ..
+            }) = options.first()
+            else {
+                return "Please provide a valid rule id".to_string();
+            };
+            match settings.delete_alert_rule(*id as i32, guild.into()) {
+                Ok(true) => format!("Alert rule #{} was removed", id),
+                Ok(false) => format!("There is no alert rule #{}", id),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption {
+            name: "history",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let mut log_limit: i64 = 10;
+            if let Some(ResolvedOption {
+                value: ResolvedValue::Integer(limit),
+                ..
+            }) = options.first()
+            {
+                log_limit = *limit;
+            }
+            match settings.get_alert_history(Some(guild.into()), 0, log_limit) {
+                Ok(history) if history.is_empty() => "No alert has fired yet".to_string(),
+                Ok(history) => history
+                    .iter()
+                    .map(|entry| {
+                        format!(
+                            "<t:{}:R>: rule #{} fired for <@{}>, streak started <t:{}:t>",
+                            entry.fired_at, entry.rule_id, entry.user_id, entry.streak_start
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                Err(err) => user_error(err),
+            }
+        }
+        _ => "Unknown subcommand".to_string(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("alerts")
+        .description("Manage alerts on activity thresholds")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add an alert rule")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "duration",
+                        "How long the state has to last, such as 90m or 4h",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::User,
+                    "user",
+                    "Only watch this user, everyone when empty",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "activity",
+                    "The activity to look for",
+                ))
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "status",
+                        "The status to look for",
+                    )
+                    .add_string_choice("Online", "online")
+                    .add_string_choice("Idle", "idle")
+                    .add_string_choice("Do not disturb", "dnd")
+                    .add_string_choice("Offline", "offline"),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "Post alerts in this channel instead of your DMs",
+                )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List the alert rules",
+        ))
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a rule")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "id", "The rule id")
+                        .required(true),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "history",
+                "Show the latest alerts that fired",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "limit",
+                    "How much data to fetch",
+                )
+                .min_int_value(1)
+                .max_int_value(25),
+            ),
+        )
+}
@@ -0,0 +1,177 @@
+use std::env;
+
+use serenity::all::{GuildId, Permissions, UserId};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::aliases;
+use crate::sessions::unix_now;
+use crate::storage::{user_error, NewActivityAlias, SettingsStore};
+
+const MAX_ALIASES: usize = 100;
+
+/// Whether `user` is listed in `BOT_OWNERS`. The rules apply to every server the bot is in,
+/// so only its owners may change them.
+fn is_owner(user: UserId) -> bool {
+    env::var("BOT_OWNERS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|id| id.trim().parse::<u64>().ok())
+        .any(|id| id == user.get())
+}
+
+/// Applies the rules to every recorded activity and tells how many were renamed.
+fn reapply(done: String, settings: &dyn SettingsStore) -> String {
+    aliases::reload();
+    match settings.apply_activity_aliases() {
+        Ok(0) => done,
+        Ok(changed) => format!("{}, {} activity names were regrouped", done, changed),
+        Err(err) => user_error(err),
+    }
+}
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    creator: UserId,
+    settings: &dyn SettingsStore,
+) -> String {
+    if guild.is_none() {
+        return "This command can only be used in a server".to_string();
+    }
+    if let Some(ResolvedOption {
+        name: "add" | "remove",
+        ..
+    }) = options.first()
+    {
+        if !is_owner(creator) {
+            return "Alias rules apply to every server, only the bot owners can change them"
+                .to_string();
+        }
+    }
+
+    match options.first() {
+        Some(ResolvedOption {
+            name: "add",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let mut alias = NewActivityAlias {
+                pattern: String::new(),
+                is_regex: false,
+                canonical: String::new(),
+                creator_id: creator.into(),
+                created_at: unix_now(),
+            };
+            for option in options {
+                match (option.name, &option.value) {
+                    ("pattern", ResolvedValue::String(value)) => {
+                        alias.pattern = value.trim().to_string()
+                    }
+                    ("canonical", ResolvedValue::String(value)) => {
+                        alias.canonical = value.trim().to_string()
+                    }
+                    ("regex", ResolvedValue::Boolean(value)) => alias.is_regex = *value,
+                    _ => {}
+                }
+            }
+            if alias.pattern.is_empty() {
+                return "Please provide a pattern".to_string();
+            }
+            if let Err(err) = aliases::validate(&alias.pattern, alias.is_regex) {
+                return err;
+            }
+            match settings.get_activity_aliases() {
+                Ok(rules) if rules.len() >= MAX_ALIASES => {
+                    return format!("There are already {} alias rules", MAX_ALIASES)
+                }
+                Ok(_) => {}
+                Err(err) => return user_error(err),
+            }
+            match settings.add_activity_alias(alias) {
+                Ok(()) => reapply("The alias rule was added".to_string(), settings),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption { name: "list", .. }) => match settings.get_activity_aliases() {
+            Ok(rules) if rules.is_empty() => "There are no alias rules".to_string(),
+            Ok(rules) => rules
+                .iter()
+                .map(|rule| {
+                    format!(
+                        "#{}: {} `{}` → {}",
+                        rule.id,
+                        if rule.is_regex { "regex" } else { "name" },
+                        rule.pattern,
+                        if rule.canonical.is_empty() {
+                            "not an activity".to_string()
+                        } else {
+                            format!("**{}**", rule.canonical)
+                        }
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            Err(err) => user_error(err),
+        },
+        Some(ResolvedOption {
+            name: "remove",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let Some(ResolvedOption {
+                value: ResolvedValue::Integer(id),
+                ..
+            }) = options.first()
+            else {
+                return "Please provide a valid rule id".to_string();
+            };
+            match settings.delete_activity_alias(*id as i32) {
+                Ok(true) => reapply(format!("Alias rule #{} was removed", id), settings),
+                Ok(false) => format!("There is no alias rule #{}", id),
+                Err(err) => user_error(err),
+            }
+        }
+        _ => "Unknown subcommand".to_string(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("aliases")
+        .description("Group activity names under one canonical name")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add an alias rule")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "pattern",
+                        "The activity name, ignoring case, or a regex",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "canonical",
+                    "The name to count it as, when empty it isn't counted as an activity",
+                ))
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "regex",
+                    "Treat the pattern as a regex matching anywhere in the name",
+                )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List the alias rules, the oldest matching rule wins",
+        ))
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a rule")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "id", "The rule id")
+                        .required(true),
+                ),
+        )
+}
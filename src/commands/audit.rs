@@ -0,0 +1,78 @@
+use serenity::all::{GuildId, Permissions};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::output::MAX_REPLY_LEN;
+use crate::storage::{user_error, SettingsStore};
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    settings: &dyn SettingsStore,
+) -> String {
+    let Some(guild) = guild else {
+        return "This command can only be used in a server".to_string();
+    };
+    let mut invoker: Option<i64> = None;
+    let mut target: Option<i64> = None;
+    let mut log_limit: i64 = 10;
+    for option in options {
+        match (option.name, &option.value) {
+            ("invoker", ResolvedValue::User(user, _)) => invoker = Some(user.id.into()),
+            ("target", ResolvedValue::User(user, _)) => target = Some(user.id.into()),
+            ("limit", ResolvedValue::Integer(value)) => log_limit = *value,
+            _ => {}
+        }
+    }
+
+    let entries = match settings.get_audit(guild.into(), invoker, target, log_limit) {
+        Ok(entries) => entries,
+        Err(err) => return user_error(err),
+    };
+    let mut res_string = String::new();
+    for entry in entries {
+        let mut line = format!(
+            "\n<t:{}:f> <@{}> used **{}** {}",
+            entry.unix_time, entry.invoker_id, entry.command, entry.options
+        );
+        match entry.outcome.as_str() {
+            "denied" => line += " (denied)",
+            "rate_limited" => line += " (rate limited)",
+            _ => {}
+        }
+        if res_string.len() + line.len() > MAX_REPLY_LEN {
+            break;
+        }
+        res_string += &line;
+    }
+    if res_string.is_empty() {
+        res_string += "Nothing was recorded in a database";
+    }
+    res_string
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("audit")
+        .description("See who used which command on whom")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "invoker",
+            "Only show commands used by this user",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "target",
+            "Only show commands that looked up this user",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "limit",
+                "How much data to fetch",
+            )
+            .min_int_value(1)
+            .max_int_value(50),
+        )
+}
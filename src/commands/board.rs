@@ -0,0 +1,99 @@
+use serenity::all::{GuildId, Permissions};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::board::SharedNowPlaying;
+use crate::storage::{user_error, NewBoard, SettingsStore};
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    state: &SharedNowPlaying,
+    settings: &dyn SettingsStore,
+) -> String {
+    let Some(guild) = guild else {
+        return "This command can only be used in a server".to_string();
+    };
+
+    match options.first() {
+        Some(ResolvedOption {
+            name: "set",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let mut channel_id: Option<i64> = None;
+            let mut include = String::new();
+            let mut exclude = String::new();
+            for option in options {
+                match (option.name, &option.value) {
+                    ("channel", ResolvedValue::Channel(channel)) => {
+                        channel_id = Some(channel.id.into())
+                    }
+                    ("include", ResolvedValue::String(value)) => include = value.to_string(),
+                    ("exclude", ResolvedValue::String(value)) => exclude = value.to_string(),
+                    _ => {}
+                }
+            }
+            let Some(channel_id) = channel_id else {
+                return "Please provide a valid channel".to_string();
+            };
+
+            if let Err(err) = settings.save_board(NewBoard {
+                guild_id: guild.into(),
+                channel_id,
+                include_activities: include,
+                exclude_activities: exclude,
+            }) {
+                return user_error(err);
+            }
+            state.lock().unwrap().mark_dirty(guild);
+            format!(
+                "The board will be kept up to date in <#{}> shortly",
+                channel_id
+            )
+        }
+        Some(ResolvedOption { name: "remove", .. }) => match settings.delete_board(guild.into()) {
+            Ok(true) => "The board was removed, its message won't be updated anymore".to_string(),
+            Ok(false) => "There is no board in this server".to_string(),
+            Err(err) => user_error(err),
+        },
+        _ => "Unknown subcommand".to_string(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("board")
+        .description("Manage the auto-updating \"now playing\" board")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "set",
+                "Post the board in a channel or change its filters",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "Channel to keep the board in",
+                )
+                .required(true),
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "include",
+                "Comma separated games to show, everything is shown when empty",
+            ))
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "exclude",
+                "Comma separated games to hide",
+            )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "remove",
+            "Stop updating the board",
+        ))
+}
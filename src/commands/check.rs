@@ -1,66 +1,54 @@
-use diesel::prelude::*;
-use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::all::GuildId;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseMessage};
 use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
 
-use crate::storage::{establish_connection, Log};
+use crate::output::{
+    cursor_option, format_option, log_lines, parse_cursor, parse_format, parse_status, render_logs,
+    status_option,
+};
+use crate::storage::{user_error, LogQuery, LogStore};
 
-pub fn run(options: &[ResolvedOption]) -> String {
-    let mut res_string;
-    let mut _user_id: i64;
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    store: &dyn LogStore,
+) -> CreateInteractionResponseMessage {
+    let message = CreateInteractionResponseMessage::new();
+    let mut _user_id: Option<i64> = None;
     let mut log_limit: Option<i64> = None;
-    if let Some(ResolvedOption {
-        value: ResolvedValue::User(user, _),
-        ..
-    }) = options.first()
-    {
-        res_string = format!("{}'s id is {}", user.tag(), user.id);
-        _user_id = user.id.into();
-    } else {
-        return "Please provide a valid user".to_string();
-    }
-    if let Some(ResolvedOption {
-        value: ResolvedValue::Integer(limit),
-        ..
-    }) = options.get(1)
-    {
-        res_string = format!(
-            "{} \n limit option was found it is set to {}",
-            res_string, limit
-        );
-        log_limit = Some(limit.clone());
+    for option in options {
+        match (option.name, &option.value) {
+            ("id", ResolvedValue::User(user, _)) => _user_id = Some(user.id.into()),
+            ("limit", ResolvedValue::Integer(limit)) => log_limit = Some(*limit),
+            _ => {}
+        }
     }
+    let Some(_user_id) = _user_id else {
+        return message.content("Please provide a valid user");
+    };
 
-    use crate::schema::logs::dsl::*;
-    match &mut establish_connection() {
-        Ok(conn) => {
-            let limit = log_limit.unwrap_or(1) as i64;
-            let results = logs
-                .filter(user_id.eq(_user_id))
-                .limit(limit)
-                .select(Log::as_select())
-                .order(id.desc())
-                .load(conn);
+    let cursor = match parse_cursor(options) {
+        Ok(cursor) => cursor,
+        Err(err) => return message.content(err),
+    };
 
-            match results {
-                Ok(records) => {
-                    res_string.clear();
-                    for record in records {
-                        res_string = format!(
-                            "{}\nStatus: {}    Activity: {}   Time: <t:{}:R>   ",
-                            res_string, record.status, record.activity, record.unix_time
-                        );
-                    }
-                    if res_string.is_empty() {
-                        res_string += "Nothing was recorded in a database";
-                    }
-                }
-                Err(err) => return err.to_string(),
-            }
-        }
-        Err(err) => return err.to_string(),
+    let limit = log_limit.unwrap_or(1);
+    let mut query = LogQuery::new()
+        .user(_user_id)
+        .guild(guild.map(i64::from))
+        .after(cursor)
+        .limit(limit);
+    if let Some(status) = parse_status(options) {
+        query = query.status(status);
     }
+    let records = match store.query(&query) {
+        Ok(records) => records,
+        Err(err) => return message.content(user_error(err)),
+    };
 
-    return res_string;
+    render_logs(&records, parse_format(options), "check", |records| {
+        log_lines(records, limit)
+    })
 }
 
 pub fn register() -> CreateCommand {
@@ -78,4 +66,76 @@ pub fn register() -> CreateCommand {
             )
             .min_int_value(1),
         )
+        .add_option(status_option())
+        .add_option(cursor_option())
+        .add_option(format_option())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serenity::all::GuildId;
+
+    use super::run;
+    use crate::commands::testing::{command, content, store, user};
+    use crate::storage::Status::{Idle, Offline, Online};
+
+    #[test]
+    fn pages_through_the_logs_of_a_user_newest_first() {
+        let store = store(&[
+            (1, Some(5), Online, "Dota 2", 100),
+            (1, Some(5), Idle, "", 200),
+            (2, Some(5), Online, "CS2", 250),
+            (1, Some(5), Offline, "", 300),
+        ]);
+        let data = command("check", &[("id", user(1)), ("limit", json!(2))]);
+        let page = content(&run(&data.options(), Some(GuildId::new(5)), &store));
+        assert!(page.contains("<t:300:R>") && page.contains("<t:200:R>"));
+        assert!(!page.contains("<t:100:R>") && !page.contains("<t:250:R>"));
+        assert!(page.ends_with("Next page: `200-2`"));
+
+        let data = command(
+            "check",
+            &[
+                ("id", user(1)),
+                ("limit", json!(2)),
+                ("cursor", json!("200-2")),
+            ],
+        );
+        let page = content(&run(&data.options(), Some(GuildId::new(5)), &store));
+        assert!(page.contains("Status: online    Activity: Dota 2   Time: <t:100:R>"));
+        assert!(!page.contains("Next page"));
+    }
+
+    #[test]
+    fn filters_by_guild_and_status() {
+        let store = store(&[
+            (1, Some(5), Online, "Dota 2", 100),
+            (1, Some(6), Online, "CS2", 200),
+            (1, None, Idle, "", 300),
+        ]);
+        let data = command("check", &[("id", user(1)), ("limit", json!(10))]);
+        let page = content(&run(&data.options(), Some(GuildId::new(5)), &store));
+        assert!(page.contains("<t:100:R>") && page.contains("<t:300:R>"));
+        assert!(!page.contains("<t:200:R>"));
+
+        let data = command(
+            "check",
+            &[
+                ("id", user(1)),
+                ("limit", json!(10)),
+                ("status", json!("idle")),
+            ],
+        );
+        let page = content(&run(&data.options(), None, &store));
+        assert!(page.contains("<t:300:R>") && !page.contains("<t:100:R>"));
+    }
+
+    #[test]
+    fn reports_an_invalid_cursor() {
+        let store = store(&[(1, Some(5), Online, "Dota 2", 100)]);
+        let data = command("check", &[("id", user(1)), ("cursor", json!("yesterday"))]);
+        let reply = content(&run(&data.options(), None, &store));
+        assert_eq!(reply, "Invalid cursor yesterday");
+    }
 }
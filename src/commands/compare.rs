@@ -0,0 +1,144 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use serenity::all::{GuildId, User};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::output::{columns, shorten};
+use crate::sessions::{
+    format_duration, load_sessions, load_totals, online_intervals, overlap_secs, unix_now,
+};
+use crate::storage::{user_error, LogQuery, LogStore};
+
+/// Keeps the table readable on mobile, Discord wraps long code block lines.
+const MAX_NAME_LEN: usize = 20;
+const MAX_SHARED: usize = 15;
+/// The rollups can't tell when both were online, so that is worked out from the raw logs of
+/// at most the last two weeks.
+const MAX_TOGETHER_DAYS: i64 = 14;
+
+pub fn run(options: &[ResolvedOption], guild: Option<GuildId>, store: &dyn LogStore) -> String {
+    let mut users: Vec<&User> = Vec::new();
+    let mut days: i64 = 30;
+    for option in options {
+        match (option.name, &option.value) {
+            ("user1" | "user2", ResolvedValue::User(user, _)) => users.push(user),
+            ("days", ResolvedValue::Integer(value)) => days = *value,
+            _ => {}
+        }
+    }
+    let [first, second] = users[..] else {
+        return "Please provide two valid users".to_string();
+    };
+    if first.id == second.id {
+        return "Please provide two different users".to_string();
+    }
+    let (first_id, second_id): (i64, i64) = (first.id.into(), second.id.into());
+
+    let to = unix_now();
+    let from = to - days * 24 * 60 * 60;
+    let guild = guild.map(i64::from);
+    let users = [first_id, second_id];
+    // Time spent together needs the sessions, the totals come from the daily rollups
+    let together_days = days.min(MAX_TOGETHER_DAYS);
+    let together_from = to - together_days * 24 * 60 * 60;
+    let query = LogQuery::new().users(&users).guild(guild);
+    let sessions = match load_sessions(store, together_from, to, query) {
+        Ok(sessions) => sessions,
+        Err(err) => return user_error(err),
+    };
+    let totals = match load_totals(store, from, to, guild, Some(&users)) {
+        Ok(totals) => totals,
+        Err(err) => return user_error(err),
+    };
+
+    let together = overlap_secs(
+        &online_intervals(&sessions, first_id),
+        &online_intervals(&sessions, second_id),
+    );
+    let online = totals.online();
+    let total = |user: i64| online.get(&user).copied().unwrap_or(0);
+
+    let mut playtime: BTreeMap<&str, HashMap<i64, i64>> = BTreeMap::new();
+    for ((user, activity), secs) in &totals.activities {
+        if *secs <= 0 {
+            continue;
+        }
+        *playtime
+            .entry(activity.as_str())
+            .or_default()
+            .entry(*user)
+            .or_default() += secs;
+    }
+    let mut shared: Vec<(&str, i64, i64)> = playtime
+        .iter()
+        .filter_map(|(activity, times)| {
+            Some((*activity, *times.get(&first_id)?, *times.get(&second_id)?))
+        })
+        .collect();
+    shared.sort_by_key(|(_, first_secs, second_secs)| Reverse(first_secs + second_secs));
+
+    let mut rows: Vec<[String; 3]> = vec![
+        [
+            String::new(),
+            shorten(&first.name, MAX_NAME_LEN, '…'),
+            shorten(&second.name, MAX_NAME_LEN, '…'),
+        ],
+        [
+            "Online".to_string(),
+            format_duration(total(first_id)),
+            format_duration(total(second_id)),
+        ],
+        [
+            match together_days < days {
+                true => format!("Together, last {}d", together_days),
+                false => "Online together".to_string(),
+            },
+            format_duration(together),
+            format_duration(together),
+        ],
+    ];
+    for (activity, first_secs, second_secs) in shared.iter().take(MAX_SHARED) {
+        rows.push([
+            shorten(activity, MAX_NAME_LEN, '…'),
+            format_duration(*first_secs),
+            format_duration(*second_secs),
+        ]);
+    }
+
+    let table = columns(&rows, &[1, 2]);
+
+    let mut res_string = format!(
+        "Comparing <@{}> and <@{}> over the last {} days\n```\n{}\n```",
+        first.id, second.id, days, table
+    );
+    if shared.is_empty() {
+        res_string += "They don't have any activities in common";
+    } else if shared.len() > MAX_SHARED {
+        res_string += &format!("And {} more shared activities", shared.len() - MAX_SHARED);
+    }
+    res_string
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("compare")
+        .description("Compare two users' online patterns and shared games")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::User, "user1", "The first user")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::User, "user2", "The second user")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "days",
+                "How many days to look back",
+            )
+            .min_int_value(1)
+            .max_int_value(90),
+        )
+}
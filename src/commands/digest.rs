@@ -0,0 +1,135 @@
+use serenity::all::{GuildId, Permissions};
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseMessage};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::digest::build;
+use crate::scheduler::{next_run, parse_schedule};
+use crate::sessions::{parse_timezone, timezone_for, unix_now};
+use crate::storage::{user_error, Digest, LogStore, SettingsStore};
+
+const DEFAULT_SCHEDULE: &str = "0 18 * * Sun";
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    store: &dyn LogStore,
+    settings: &dyn SettingsStore,
+) -> CreateInteractionResponseMessage {
+    let message = CreateInteractionResponseMessage::new();
+    let Some(guild) = guild else {
+        return message.content("This command can only be used in a server");
+    };
+
+    match options.first() {
+        Some(ResolvedOption {
+            name: "set",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let mut channel_id: Option<i64> = None;
+            let mut schedule = DEFAULT_SCHEDULE.to_string();
+            let mut timezone = timezone_for(settings, Some(guild.into()), None).to_string();
+            for option in options {
+                match (option.name, &option.value) {
+                    ("channel", ResolvedValue::Channel(channel)) => {
+                        channel_id = Some(channel.id.into())
+                    }
+                    ("schedule", ResolvedValue::String(value)) => schedule = value.to_string(),
+                    ("timezone", ResolvedValue::String(value)) => timezone = value.to_string(),
+                    _ => {}
+                }
+            }
+            let Some(channel_id) = channel_id else {
+                return message.content("Please provide a valid channel");
+            };
+            let parsed = match parse_schedule(&schedule) {
+                Ok(parsed) => parsed,
+                Err(err) => return message.content(err),
+            };
+            let tz = match parse_timezone(&timezone) {
+                Ok(tz) => tz,
+                Err(err) => return message.content(err),
+            };
+
+            let now = unix_now();
+            if let Err(err) = settings.save_digest(Digest {
+                guild_id: guild.into(),
+                channel_id,
+                schedule,
+                timezone: tz.to_string(),
+                last_sent: now,
+            }) {
+                return message.content(user_error(err));
+            }
+            match next_run(&parsed, tz, now) {
+                Some(next) => message.content(format!(
+                    "The digest will be posted in <#{}>, next time <t:{}:F>",
+                    channel_id, next
+                )),
+                None => message.content("The digest was saved, but its schedule never runs"),
+            }
+        }
+        Some(ResolvedOption {
+            name: "preview", ..
+        }) => {
+            let tz = match settings.get_digest(guild.into()) {
+                Ok(Some(config)) => parse_timezone(&config.timezone)
+                    .unwrap_or_else(|_| timezone_for(settings, Some(guild.into()), None)),
+                Ok(None) => timezone_for(settings, Some(guild.into()), None),
+                Err(err) => return message.content(user_error(err)),
+            };
+            match build(store, guild.into(), tz, unix_now()) {
+                Ok(embed) => message.embed(embed),
+                Err(err) => message.content(user_error(err)),
+            }
+        }
+        Some(ResolvedOption { name: "remove", .. }) => match settings.delete_digest(guild.into()) {
+            Ok(true) => message.content("The weekly digest won't be posted anymore"),
+            Ok(false) => message.content("There is no digest in this server"),
+            Err(err) => message.content(user_error(err)),
+        },
+        _ => message.content("Unknown subcommand"),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("digest")
+        .description("Manage the scheduled weekly digest")
+        .default_member_permissions(Permissions::MANAGE_GUILD)
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "set",
+                "Post the digest in a channel on a schedule",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "Channel to post the digest in",
+                )
+                .required(true),
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "schedule",
+                "Cron schedule: minute hour day month weekday, 0 18 * * Sun by default",
+            ))
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::String,
+                "timezone",
+                "Time zone of the schedule, the server's time zone by default",
+            )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "preview",
+            "Show the digest for the last 7 days now",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "remove",
+            "Stop posting the digest",
+        ))
+}
@@ -46,13 +46,10 @@ pub fn run(options: &[ResolvedOption]) -> String {
             *val += format!("\n Error occured while evaluating an expression! {}", err).as_str();
         }));
         inter.system_function_executor = Some(Box::new(|name, args| {
-            match name.as_str() {
-                "debug_reply!" => {
-                    let mut val = script_output.lock().unwrap();
-                    let arg = String::from(args.first().unwrap());
-                    *val += format!("\n Reply from debug_reply! function: {}", arg).as_str();
-                }
-                _ => {}
+            if name.as_str() == "debug_reply!" {
+                let mut val = script_output.lock().unwrap();
+                let arg = String::from(args.first().unwrap());
+                *val += format!("\n Reply from debug_reply! function: {}", arg).as_str();
             }
             None
         }));
@@ -61,7 +58,7 @@ pub fn run(options: &[ResolvedOption]) -> String {
     let val = script_output.lock().unwrap();
     res_string += val.as_str();
 
-    return res_string;
+    res_string
 }
 
 pub fn register() -> CreateCommand {
@@ -0,0 +1,119 @@
+use std::io::{self, Write};
+
+use serenity::all::{GuildId, Permissions, UserId};
+use serenity::builder::{
+    CreateAttachment, CreateCommand, CreateCommandOption, EditInteractionResponse,
+};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::export::{export_logs, parse_date_range, ExportFormat};
+use crate::output::{parse_status, status_option};
+use crate::sessions::timezone_for;
+use crate::storage::{LogQuery, SharedLogStore, SharedSettingsStore};
+
+/// Discord refuses attachments above 10 MiB on servers without boosts.
+const MAX_ATTACHMENT_LEN: usize = 10 * 1024 * 1024;
+
+/// Buffers the export, failing as soon as it grows too big to be attached.
+struct AttachmentWriter(Vec<u8>);
+
+impl Write for AttachmentWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0.len() + buf.len() > MAX_ATTACHMENT_LEN {
+            return Err(io::Error::other(
+                "The export is too large for Discord, narrow it down or use the export CLI",
+            ));
+        }
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the options and returns the export to run once the reply is deferred, building a
+/// file of up to 10 MiB takes longer than Discord waits for a reply.
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    viewer: UserId,
+    store: SharedLogStore,
+    settings: SharedSettingsStore,
+) -> impl FnOnce() -> EditInteractionResponse + Send + 'static {
+    let mut query = LogQuery::new().guild(guild.map(i64::from));
+    let mut format = ExportFormat::Csv;
+    let mut from: Option<String> = None;
+    let mut to: Option<String> = None;
+    for option in options {
+        match (option.name, &option.value) {
+            ("user", ResolvedValue::User(user, _)) => query = query.user(user.id.into()),
+            ("activity", ResolvedValue::String(value)) => query = query.activity(value),
+            ("from", ResolvedValue::String(value)) => from = Some(value.to_string()),
+            ("to", ResolvedValue::String(value)) => to = Some(value.to_string()),
+            ("format", ResolvedValue::String(value)) => {
+                format = ExportFormat::parse(value).unwrap_or(ExportFormat::Csv)
+            }
+            _ => {}
+        }
+    }
+    if let Some(status) = parse_status(options) {
+        query = query.status(status);
+    }
+
+    move || {
+        let message = EditInteractionResponse::new();
+        if guild.is_none() {
+            return message.content("This command can only be used in a server");
+        }
+        let tz = timezone_for(&*settings, guild.map(i64::from), Some(viewer.into()));
+        match parse_date_range(from.as_deref(), to.as_deref(), tz) {
+            Ok((from, to)) => query = query.between(from, to),
+            Err(err) => return message.content(err),
+        }
+
+        let mut file = AttachmentWriter(Vec::new());
+        match export_logs(&*store, &mut file, &query, format) {
+            Ok(0) => message.content("Nothing was recorded in a database"),
+            Ok(count) => message
+                .content(format!("Exported {} logs", count))
+                .new_attachment(CreateAttachment::bytes(file.0, format.file_name())),
+            Err(err) => message.content(err),
+        }
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("export")
+        .description("Export the logs of everyone in a compressed file")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .dm_permission(false)
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "Only export the logs of this user",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "activity",
+            "Only export the logs of this activity",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "from",
+            "First day to export, as YYYY-MM-DD",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "to",
+            "Last day to export, as YYYY-MM-DD",
+        ))
+        .add_option(status_option())
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "format", "File format")
+                .add_string_choice("CSV", "csv")
+                .add_string_choice("JSON", "json")
+                .add_string_choice("NDJSON", "ndjson"),
+        )
+}
@@ -1,71 +1,64 @@
-use diesel::prelude::*;
-use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::all::GuildId;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseMessage};
 use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
 
-use crate::storage::{establish_connection, Log};
+use crate::output::{
+    cursor_option, format_option, log_lines, parse_cursor, parse_format, parse_status, render_logs,
+    status_option,
+};
+use crate::storage::{user_error, LogQuery, LogStore};
 
-pub fn run(options: &[ResolvedOption]) -> String {
-    let mut res_string;
-    let mut _user_id: i64;
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    store: &dyn LogStore,
+) -> CreateInteractionResponseMessage {
+    let message = CreateInteractionResponseMessage::new();
+    let mut _user_id: Option<i64> = None;
     let mut log_limit: Option<i64> = None;
     let mut activity_name: String = String::new();
-    if let Some(ResolvedOption {
-        value: ResolvedValue::User(user, _),
-        ..
-    }) = options.first()
-    {
-        res_string = format!("{}'s id is {}", user.tag(), user.id);
-        _user_id = user.id.into();
-    } else {
-        return "Please provide a valid user".to_string();
-    }
-    if let Some(ResolvedOption {
-        value: ResolvedValue::Integer(limit),
-        ..
-    }) = options.get(2)
-    {
-        log_limit = Some(limit.clone());
-    }
-    if let Some(ResolvedOption {
-        value: ResolvedValue::String(_activity),
-        ..
-    }) = options.get(1)
-    {
-        activity_name = String::from(*_activity);
-    }
-
-    use crate::schema::logs::dsl::*;
-    match &mut establish_connection() {
-        Ok(conn) => {
-            let limit = log_limit.unwrap_or(1) as i64;
-            let results = logs
-                .filter(user_id.eq(_user_id))
-                .filter(activity.eq(activity_name))
-                .limit(limit)
-                .select(Log::as_select())
-                .order(id.desc())
-                .load(conn);
-
-            match results {
-                Ok(records) => {
-                    res_string.clear();
-                    for record in records {
-                        res_string = format!(
-                            "{}\nStatus: {}    Activity: {}   Time: <t:{}:R>   ",
-                            res_string, record.status, record.activity, record.unix_time
-                        );
-                    }
-                    if res_string.is_empty() {
-                        res_string += "Nothing was recorded in a database";
-                    }
-                }
-                Err(err) => return err.to_string(),
+    let mut fuzzy = false;
+    for option in options {
+        match (option.name, &option.value) {
+            ("id", ResolvedValue::User(user, _)) => _user_id = Some(user.id.into()),
+            ("activity", ResolvedValue::String(_activity)) => {
+                activity_name = String::from(*_activity)
             }
+            ("fuzzy", ResolvedValue::Boolean(value)) => fuzzy = *value,
+            ("limit", ResolvedValue::Integer(limit)) => log_limit = Some(*limit),
+            _ => {}
         }
-        Err(err) => return err.to_string(),
     }
+    let Some(_user_id) = _user_id else {
+        return message.content("Please provide a valid user");
+    };
+    let cursor = match parse_cursor(options) {
+        Ok(cursor) => cursor,
+        Err(err) => return message.content(err),
+    };
 
-    return res_string;
+    let limit = log_limit.unwrap_or(1);
+    let mut query = LogQuery::new()
+        .user(_user_id)
+        .guild(guild.map(i64::from))
+        .after(cursor)
+        .limit(limit);
+    query = if fuzzy {
+        query.activity_like(&activity_name)
+    } else {
+        query.activity(&activity_name)
+    };
+    if let Some(status) = parse_status(options) {
+        query = query.status(status);
+    }
+    let records = match store.query(&query) {
+        Ok(records) => records,
+        Err(err) => return message.content(user_error(err)),
+    };
+
+    render_logs(&records, parse_format(options), "filter", |records| {
+        log_lines(records, limit)
+    })
 }
 
 pub fn register() -> CreateCommand {
@@ -79,6 +72,12 @@ pub fn register() -> CreateCommand {
             CreateCommandOption::new(CommandOptionType::String, "activity", "Activity type")
                 .required(true),
         )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "fuzzy",
+            "Match activities containing the text, ignoring case",
+        ))
+        .add_option(status_option())
         .add_option(
             CreateCommandOption::new(
                 CommandOptionType::Integer,
@@ -87,4 +86,73 @@ pub fn register() -> CreateCommand {
             )
             .min_int_value(1),
         )
+        .add_option(cursor_option())
+        .add_option(format_option())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::run;
+    use crate::commands::testing::{command, content, store, user};
+    use crate::storage::Status::{Idle, Online};
+
+    fn logs() -> crate::storage::memory::MemoryStore {
+        store(&[
+            (1, Some(5), Online, "Dota 2", 100),
+            (1, Some(5), Online, "Counter-Strike 2", 200),
+            (1, Some(5), Idle, "Dota 2", 300),
+            (2, Some(5), Online, "Dota 2", 400),
+        ])
+    }
+
+    #[test]
+    fn matches_the_exact_activity_of_the_user() {
+        let store = logs();
+        let data = command(
+            "filter",
+            &[
+                ("id", user(1)),
+                ("activity", json!("Dota 2")),
+                ("limit", json!(5)),
+            ],
+        );
+        let page = content(&run(&data.options(), None, &store));
+        assert!(page.contains("<t:100:R>") && page.contains("<t:300:R>"));
+        assert!(!page.contains("<t:200:R>") && !page.contains("<t:400:R>"));
+
+        let data = command("filter", &[("id", user(1)), ("activity", json!("dota"))]);
+        let reply = content(&run(&data.options(), None, &store));
+        assert_eq!(reply, "Nothing was recorded in a database");
+    }
+
+    #[test]
+    fn fuzzy_matches_ignore_case_and_respect_the_status() {
+        let store = logs();
+        let data = command(
+            "filter",
+            &[
+                ("id", user(1)),
+                ("activity", json!("STRIKE")),
+                ("fuzzy", json!(true)),
+                ("limit", json!(5)),
+            ],
+        );
+        let page = content(&run(&data.options(), None, &store));
+        assert!(page.contains("<t:200:R>") && !page.contains("<t:100:R>"));
+
+        let data = command(
+            "filter",
+            &[
+                ("id", user(1)),
+                ("activity", json!("dota")),
+                ("fuzzy", json!(true)),
+                ("status", json!("idle")),
+                ("limit", json!(5)),
+            ],
+        );
+        let page = content(&run(&data.options(), None, &store));
+        assert!(page.contains("<t:300:R>") && !page.contains("<t:100:R>"));
+    }
 }
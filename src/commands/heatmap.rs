@@ -0,0 +1,128 @@
+use chrono::{Datelike, Timelike};
+use serenity::all::{GuildId, UserId};
+use serenity::builder::{
+    CreateAttachment, CreateCommand, CreateCommandOption, CreateInteractionResponseMessage,
+};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::render::{mix, text_width, Canvas, BACKGROUND, GRID, TEXT};
+use crate::sessions::{load_sessions, split_by_hour, timezone_for, unix_now};
+use crate::storage::{user_error, LogQuery, LogStore, SettingsStore};
+
+const CELL_WIDTH: i64 = 28;
+const CELL_HEIGHT: i64 = 22;
+const GAP: i64 = 2;
+const LEFT: i64 = 48;
+const TOP: i64 = 48;
+const COLD: [u8; 3] = [0x1f, 0x3d, 0x2b];
+const HOT: [u8; 3] = [0x57, 0xf2, 0x87];
+const WEEKDAYS: [&str; 7] = ["MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    viewer: UserId,
+    store: &dyn LogStore,
+    settings: &dyn SettingsStore,
+) -> CreateInteractionResponseMessage {
+    let mut member: Option<i64> = None;
+    let mut days: i64 = 28;
+    for option in options {
+        match (option.name, &option.value) {
+            ("user", ResolvedValue::User(user, _)) => member = Some(user.id.into()),
+            ("days", ResolvedValue::Integer(value)) => days = *value,
+            _ => {}
+        }
+    }
+    let message = CreateInteractionResponseMessage::new();
+
+    let to = unix_now();
+    let from = to - days * 24 * 60 * 60;
+    let mut query = LogQuery::new().guild(guild.map(i64::from));
+    if let Some(member) = member {
+        query = query.user(member);
+    }
+    let sessions = match load_sessions(store, from, to, query) {
+        Ok(sessions) => sessions,
+        Err(err) => return message.content(user_error(err)),
+    };
+
+    let tz = timezone_for(settings, guild.map(i64::from), Some(viewer.into()));
+    let mut grid = [[0i64; 24]; 7];
+    for session in sessions
+        .iter()
+        .filter(|session| !session.status.is_offline())
+    {
+        split_by_hour(session.start, session.end, tz, |local, secs| {
+            grid[local.weekday().num_days_from_monday() as usize][local.hour() as usize] += secs;
+        });
+    }
+
+    let subject = match member {
+        Some(member) => format!("<@{}>", member),
+        None => "everyone".to_string(),
+    };
+    if grid.iter().flatten().all(|secs| *secs == 0) {
+        return message.content(format!(
+            "Nothing was recorded for {} in the last {} days",
+            subject, days
+        ));
+    }
+
+    match draw(&grid, &format!("ONLINE TIME, LAST {} DAYS ({})", days, tz)) {
+        Ok(png) => message
+            .content(format!(
+                "When {} was online over the last {} days",
+                subject, days
+            ))
+            .add_file(CreateAttachment::bytes(png, "heatmap.png")),
+        Err(err) => message.content(err),
+    }
+}
+
+fn draw(grid: &[[i64; 24]; 7], title: &str) -> Result<Vec<u8>, String> {
+    let width = LEFT + 24 * (CELL_WIDTH + GAP) + 16;
+    let height = TOP + 7 * (CELL_HEIGHT + GAP) + 16;
+    let mut canvas = Canvas::new(width as u32, height as u32, BACKGROUND);
+    let max = grid.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+    canvas.draw_text(LEFT, 10, title, 2, TEXT);
+    for hour in (0..24).step_by(3) {
+        let label = format!("{:02}", hour);
+        let x = LEFT + hour as i64 * (CELL_WIDTH + GAP) + (CELL_WIDTH - text_width(&label, 1)) / 2;
+        canvas.draw_text(x, TOP - 12, &label, 1, TEXT);
+    }
+    for (day, hours) in grid.iter().enumerate() {
+        let y = TOP + day as i64 * (CELL_HEIGHT + GAP);
+        canvas.draw_text(12, y + (CELL_HEIGHT - 7) / 2, WEEKDAYS[day], 1, TEXT);
+        for (hour, secs) in hours.iter().enumerate() {
+            let color = if *secs == 0 {
+                GRID
+            } else {
+                mix(COLD, HOT, *secs as f64 / max as f64)
+            };
+            let x = LEFT + hour as i64 * (CELL_WIDTH + GAP);
+            canvas.fill_rect(x, y, CELL_WIDTH, CELL_HEIGHT, color);
+        }
+    }
+    canvas.encode_png()
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("heatmap")
+        .description("Weekday and hour heatmap of online time")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "The user to lookup, the whole server when empty",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "days",
+                "How many days to look back",
+            )
+            .min_int_value(1)
+            .max_int_value(180),
+        )
+}
@@ -1,4 +1,21 @@
+pub mod admin;
+pub mod alerts;
+pub mod aliases;
+pub mod audit;
+pub mod board;
 pub mod check;
+pub mod compare;
+pub mod digest;
 pub mod execute;
+pub mod export;
 pub mod filter;
+pub mod heatmap;
+pub mod privacy;
+pub mod settings;
+pub mod show_activity;
+#[cfg(test)]
+pub mod testing;
+pub mod timeline;
+pub mod together;
+pub mod watch;
 pub mod whoplayed;
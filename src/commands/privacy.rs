@@ -0,0 +1,38 @@
+use serenity::all::UserId;
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption};
+
+use crate::sessions::unix_now;
+use crate::storage::{user_error, SettingsStore};
+
+pub fn run(options: &[ResolvedOption], user: UserId, settings: &dyn SettingsStore) -> String {
+    match options.first() {
+        Some(ResolvedOption { name: "optout", .. }) => {
+            match settings.opt_out(user.into(), unix_now()) {
+                Ok(()) => "You opted out, nobody can watch you anymore".to_string(),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption { name: "optin", .. }) => match settings.opt_in(user.into()) {
+            Ok(true) => "You opted back in".to_string(),
+            Ok(false) => "You haven't opted out".to_string(),
+            Err(err) => user_error(err),
+        },
+        _ => "Unknown subcommand".to_string(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("privacy")
+        .description("Control what others can do with your activity")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "optout",
+            "Stop others from watching you and remove their watches",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "optin",
+            "Allow others to watch you again",
+        ))
+}
@@ -0,0 +1,110 @@
+use serenity::all::{GuildId, Permissions, UserId};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::sessions::{configured_timezone, parse_timezone, timezone_for};
+use crate::storage::{user_error, SettingsStore};
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    user: UserId,
+    permissions: Option<Permissions>,
+    settings: &dyn SettingsStore,
+) -> String {
+    match options.first() {
+        Some(ResolvedOption {
+            name: "timezone",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let mut name = String::new();
+            let mut server = false;
+            for option in options {
+                match (option.name, &option.value) {
+                    ("name", ResolvedValue::String(value)) => name = value.trim().to_string(),
+                    ("server", ResolvedValue::Boolean(value)) => server = *value,
+                    _ => {}
+                }
+            }
+            // "default" clears the setting, falling back to the server or bot default
+            let timezone = if name.eq_ignore_ascii_case("default") {
+                None
+            } else {
+                match parse_timezone(&name) {
+                    Ok(tz) => Some(tz.to_string()),
+                    Err(err) => return err,
+                }
+            };
+
+            let res = if server {
+                let Some(guild) = guild else {
+                    return "The server time zone can only be set in a server".to_string();
+                };
+                if !permissions.is_some_and(|permissions| permissions.manage_guild()) {
+                    return "You need the Manage Server permission to change the server time zone"
+                        .to_string();
+                }
+                settings.set_guild_timezone(guild.into(), timezone)
+            } else {
+                settings.set_user_timezone(user.into(), timezone)
+            };
+            match res {
+                Ok(()) => format!(
+                    "Statistics will now be shown in {}",
+                    timezone_for(settings, guild.map(i64::from), Some(user.into()))
+                ),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption { name: "show", .. }) => {
+            let user_timezone = match settings.get_user_timezone(user.into()) {
+                Ok(timezone) => timezone,
+                Err(err) => return user_error(err),
+            };
+            let guild_timezone = match guild.map(|guild| settings.get_guild_timezone(guild.into()))
+            {
+                Some(Ok(timezone)) => timezone,
+                Some(Err(err)) => return user_error(err),
+                None => None,
+            };
+            format!(
+                "Your time zone: {}\nServer time zone: {}\nDefault time zone: {}",
+                user_timezone.unwrap_or("not set".to_string()),
+                guild_timezone.unwrap_or("not set".to_string()),
+                configured_timezone()
+            )
+        }
+        _ => "Unknown subcommand".to_string(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("settings")
+        .description("Change how statistics are shown")
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "timezone",
+                "Set the time zone used for daily and hourly statistics",
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "name",
+                    "Time zone such as Europe/Kyiv, or default to unset it",
+                )
+                .required(true),
+            )
+            .add_sub_option(CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "server",
+                "Set the time zone of the whole server instead of your own",
+            )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "show",
+            "Show the current settings",
+        ))
+}
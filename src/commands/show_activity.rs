@@ -0,0 +1,82 @@
+use std::cmp::Reverse;
+
+use serenity::all::{CommandType, CreateEmbed, CreateEmbedFooter, GuildId, ResolvedTarget};
+use serenity::builder::{CreateCommand, CreateInteractionResponseMessage};
+
+use crate::sessions::{format_duration, load_totals, unix_now};
+use crate::storage::{user_error, LogQuery, LogStore, DAY_SECS};
+
+pub const NAME: &str = "Show activity";
+const DAYS: i64 = 7;
+const MAX_ACTIVITIES: usize = 10;
+
+pub fn run(
+    target: Option<ResolvedTarget>,
+    guild: Option<GuildId>,
+    store: &dyn LogStore,
+) -> CreateInteractionResponseMessage {
+    let message = CreateInteractionResponseMessage::new();
+    let Some(ResolvedTarget::User(user, _)) = target else {
+        return message.content("Please provide a valid user");
+    };
+    let user_id: i64 = user.id.into();
+    let query = LogQuery::new().user(user_id).guild(guild.map(i64::from));
+
+    let last = match store.first(query.clone()) {
+        Ok(Some(last)) => last,
+        Ok(None) => {
+            return message.content(format!("Nothing was recorded for <@{}>", user.id));
+        }
+        Err(err) => return message.content(user_error(err)),
+    };
+    let last_activity = match store.first(query.clone().any_activity()) {
+        Ok(last_activity) => last_activity,
+        Err(err) => return message.content(user_error(err)),
+    };
+
+    let now = unix_now();
+    let from = now - DAYS * DAY_SECS;
+    let playtime = match load_totals(store, from, now, guild.map(i64::from), Some(&[user_id])) {
+        Ok(totals) => totals.playtime(),
+        Err(err) => return message.content(user_error(err)),
+    };
+    let mut playtime: Vec<(String, i64)> = playtime.into_iter().collect();
+    playtime.sort_by_key(|(name, secs)| (Reverse(*secs), name.clone()));
+
+    let last_seen = if last.status.is_offline() {
+        format!("<t:{}:R>", last.unix_time)
+    } else {
+        "Now".to_string()
+    };
+    let last_activity = match last_activity {
+        Some(log) if log.id == last.id => format!("{} (now)", log.activity),
+        Some(log) => format!("{} (<t:{}:R>)", log.activity, log.unix_time),
+        None => "Nothing recorded".to_string(),
+    };
+    let mut playtime_lines: Vec<String> = playtime
+        .iter()
+        .take(MAX_ACTIVITIES)
+        .map(|(name, secs)| format!("**{}**: {}", name, format_duration(*secs)))
+        .collect();
+    if playtime_lines.is_empty() {
+        playtime_lines.push("Nothing played".to_string());
+    }
+
+    let embed = CreateEmbed::new()
+        .title(format!("Activity of {}", user.name))
+        .thumbnail(user.face())
+        .field("Status", last.status.label(), true)
+        .field("Last seen", last_seen, true)
+        .field("Last activity", last_activity, false)
+        .field(
+            format!("Playtime over the last {} days", DAYS),
+            playtime_lines.join("\n"),
+            false,
+        )
+        .footer(CreateEmbedFooter::new(format!("User id {}", user.id)));
+    message.embed(embed)
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new(NAME).kind(CommandType::User)
+}
@@ -0,0 +1,86 @@
+//! Helpers for running commands in tests without Discord.
+
+use serde_json::{json, Value};
+use serenity::all::CommandData;
+use serenity::builder::CreateInteractionResponseMessage;
+
+use crate::storage::memory::MemoryStore;
+use crate::storage::{ActivityAlias, LogStore, NewLog, Status};
+
+/// The value of a user option.
+pub fn user(id: i64) -> Value {
+    json!({ "user": id.to_string() })
+}
+
+/// The data of a `name` command invoked with `options`, given as `(name, value)` pairs.
+pub fn command(name: &str, options: &[(&str, Value)]) -> CommandData {
+    let mut users = serde_json::Map::new();
+    let options: Vec<Value> = options
+        .iter()
+        .map(|(name, value)| match value {
+            Value::Object(user) => {
+                let id = user["user"].clone();
+                let resolved = json!({ "id": id, "username": "user", "discriminator": "0" });
+                users.insert(id.as_str().unwrap().to_string(), resolved);
+                json!({ "name": name, "type": 6, "value": id })
+            }
+            Value::Bool(_) => json!({ "name": name, "type": 5, "value": value }),
+            Value::Number(_) => json!({ "name": name, "type": 4, "value": value }),
+            _ => json!({ "name": name, "type": 3, "value": value }),
+        })
+        .collect();
+    serde_json::from_value(json!({
+        "id": "1",
+        "name": name,
+        "type": 1,
+        "options": options,
+        "resolved": { "users": users },
+    }))
+    .unwrap()
+}
+
+/// The text of a reply.
+pub fn content(message: &CreateInteractionResponseMessage) -> String {
+    serde_json::to_value(message).unwrap()["content"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// A store holding `logs`, given as `(user, guild, status, activity, time)`.
+pub fn store(logs: &[(i64, Option<i64>, Status, &str, i64)]) -> MemoryStore {
+    store_with_aliases(&[], logs)
+}
+
+/// A store holding `logs` and renaming their activities after the `(pattern, canonical)`
+/// alias rules.
+pub fn store_with_aliases(
+    aliases: &[(&str, &str)],
+    logs: &[(i64, Option<i64>, Status, &str, i64)],
+) -> MemoryStore {
+    let aliases: Vec<ActivityAlias> = aliases
+        .iter()
+        .enumerate()
+        .map(|(index, (pattern, canonical))| ActivityAlias {
+            id: index as i32 + 1,
+            pattern: pattern.to_string(),
+            is_regex: false,
+            canonical: canonical.to_string(),
+            creator_id: 0,
+            created_at: 0,
+        })
+        .collect();
+    let store = MemoryStore::with_aliases(&aliases);
+    for (user_id, guild_id, status, activity, unix_time) in logs {
+        store
+            .insert(NewLog {
+                user_id: *user_id,
+                status: *status,
+                activity: activity.to_string(),
+                unix_time: *unix_time,
+                guild_id: *guild_id,
+            })
+            .unwrap();
+    }
+    store
+}
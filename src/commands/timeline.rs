@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, TimeZone, Timelike};
+use chrono_tz::Tz;
+use serenity::all::{GuildId, UserId};
+use serenity::builder::{
+    CreateAttachment, CreateCommand, CreateCommandOption, CreateInteractionResponseMessage,
+};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::output::shorten;
+use crate::render::{mix, text_width, Canvas, Rgb, BACKGROUND, GRID, TEXT};
+use crate::sessions::{
+    activity_sessions, day_bounds, load_sessions, split_by_hour, timezone_for, unix_now, Session,
+};
+use crate::storage::{user_error, LogQuery, LogStore, SettingsStore, Status};
+
+const LEFT: i64 = 150;
+const TOP: i64 = 52;
+const HOUR_WIDTH: i64 = 36;
+const LANE_HEIGHT: i64 = 24;
+const LANE_GAP: i64 = 6;
+const MAX_ACTIVITY_LANES: usize = 12;
+/// Labels are cut with a dot, the chart font has no ellipsis.
+const MAX_LABEL_LEN: usize = 22;
+const ACTIVITY: Rgb = [0x58, 0x65, 0xf2];
+const HATCH: Rgb = [0x5c, 0x60, 0x67];
+const STATUSES: [(&str, Rgb); 4] = [
+    ("online", [0x23, 0xa5, 0x5a]),
+    ("idle", [0xf0, 0xb2, 0x32]),
+    ("dnd", [0xf2, 0x3f, 0x43]),
+    ("offline", [0x80, 0x84, 0x8e]),
+];
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    viewer: UserId,
+    store: &dyn LogStore,
+    settings: &dyn SettingsStore,
+) -> CreateInteractionResponseMessage {
+    let message = CreateInteractionResponseMessage::new();
+    let mut member: Option<(i64, String)> = None;
+    let mut date: Option<&str> = None;
+    for option in options {
+        match (option.name, &option.value) {
+            ("user", ResolvedValue::User(user, _)) => {
+                member = Some((user.id.into(), user.name.clone()))
+            }
+            ("date", ResolvedValue::String(value)) => date = Some(value),
+            _ => {}
+        }
+    }
+    let Some((member, name)) = member else {
+        return message.content("Please provide a valid user");
+    };
+
+    let tz = timezone_for(settings, guild.map(i64::from), Some(viewer.into()));
+    let day = match date {
+        Some(date) => match NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d") {
+            Ok(day) => day,
+            Err(_) => return message.content("Please provide the date as YYYY-MM-DD"),
+        },
+        None => tz.timestamp_opt(unix_now(), 0).unwrap().date_naive(),
+    };
+    let Some((from, to)) = day_bounds(day, tz) else {
+        return message.content("That date doesn't exist in the configured time zone");
+    };
+    if from > unix_now() {
+        return message.content("That day hasn't happened yet");
+    }
+    // The rest of today is left blank
+    let until = to.min(unix_now());
+
+    let sessions = match load_sessions(
+        store,
+        from,
+        until,
+        LogQuery::new().user(member).guild(guild.map(i64::from)),
+    ) {
+        Ok(sessions) => sessions,
+        Err(err) => return message.content(user_error(err)),
+    };
+    let gaps = match store.offline_gaps(from, until) {
+        Ok(gaps) => gaps,
+        Err(err) => return message.content(user_error(err)),
+    };
+    if sessions.is_empty() {
+        return message.content(format!("Nothing was recorded for <@{}> on {}", member, day));
+    }
+
+    let title = format!("{} - {} ({})", name, day, tz);
+    match draw(&title, from, to, tz, &sessions, &gaps) {
+        Ok(png) => message
+            .content(format!("Timeline of <@{}> on {}", member, day))
+            .add_file(CreateAttachment::bytes(png, "timeline.png")),
+        Err(err) => message.content(err),
+    }
+}
+
+fn status_color(status: Status) -> Rgb {
+    STATUSES
+        .iter()
+        .find(|(name, _)| *name == status.as_str())
+        .map(|(_, color)| *color)
+        .unwrap_or(STATUSES[3].1)
+}
+
+fn draw(
+    title: &str,
+    from: i64,
+    to: i64,
+    tz: Tz,
+    sessions: &[Session],
+    gaps: &[(i64, i64)],
+) -> Result<Vec<u8>, String> {
+    // Activities in order of their first appearance, one lane each
+    let games = activity_sessions(sessions);
+    let mut lanes: Vec<&str> = Vec::new();
+    let mut by_lane: HashMap<&str, Vec<&Session>> = HashMap::new();
+    for session in &games {
+        if !lanes.contains(&session.activity.as_str()) {
+            lanes.push(&session.activity);
+        }
+        by_lane.entry(&session.activity).or_default().push(session);
+    }
+    lanes.truncate(MAX_ACTIVITY_LANES);
+
+    let day_width = 24 * HOUR_WIDTH;
+    let lane_count = lanes.len() as i64 + 1;
+    let bottom = TOP + lane_count * (LANE_HEIGHT + LANE_GAP);
+    let width = LEFT + day_width + 20;
+    let height = bottom + 30;
+    let mut canvas = Canvas::new(width as u32, height as u32, BACKGROUND);
+    let x_of = |time: i64| LEFT + (time - from) * day_width / (to - from);
+
+    canvas.draw_text(12, 10, title, 2, TEXT);
+    split_by_hour(from, to, tz, |local, _| {
+        let x = x_of(local.timestamp());
+        canvas.fill_rect(x, TOP - 4, 1, bottom - TOP, GRID);
+        if local.hour() % 2 == 0 {
+            let text = format!("{:02}", local.hour());
+            canvas.draw_text(x - text_width(&text, 1) / 2, TOP - 16, &text, 1, TEXT);
+        }
+    });
+
+    let lane_y = |lane: i64| TOP + lane * (LANE_HEIGHT + LANE_GAP);
+    let label_y = |lane: i64| lane_y(lane) + (LANE_HEIGHT - 7) / 2;
+    canvas.draw_text(12, label_y(0), "STATUS", 1, TEXT);
+    for session in sessions {
+        let (start, end) = (x_of(session.start), x_of(session.end));
+        canvas.fill_rect(
+            start,
+            lane_y(0),
+            (end - start).max(1),
+            LANE_HEIGHT,
+            status_color(session.status),
+        );
+    }
+    for (index, lane) in lanes.iter().enumerate() {
+        let lane_index = index as i64 + 1;
+        canvas.draw_text(
+            12,
+            label_y(lane_index),
+            &shorten(lane, MAX_LABEL_LEN, '.'),
+            1,
+            TEXT,
+        );
+        for session in &by_lane[lane] {
+            let (start, end) = (x_of(session.start), x_of(session.end));
+            canvas.fill_rect(
+                start,
+                lane_y(lane_index),
+                (end - start).max(1),
+                LANE_HEIGHT,
+                ACTIVITY,
+            );
+        }
+    }
+
+    // Diagonal stripes over the stretches the bot wasn't running to record anything
+    for (gap_start, gap_end) in gaps {
+        for x in x_of(*gap_start)..x_of(*gap_end) {
+            for y in TOP - 4..bottom - LANE_GAP {
+                let color = if (x + y).rem_euclid(8) < 2 {
+                    HATCH
+                } else {
+                    mix(BACKGROUND, HATCH, 0.2)
+                };
+                canvas.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    let mut legend_x = LEFT;
+    let legend_y = bottom + 8;
+    for (name, color) in STATUSES
+        .iter()
+        .copied()
+        .chain([("activity", ACTIVITY), ("bot offline", HATCH)])
+    {
+        canvas.fill_rect(legend_x, legend_y, 10, 10, color);
+        canvas.draw_text(legend_x + 14, legend_y + 2, name, 1, TEXT);
+        legend_x += 14 + text_width(name, 1) + 18;
+    }
+
+    canvas.encode_png()
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("timeline")
+        .description("Draw a timeline of a user's statuses and activities for a day")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::User, "user", "The user to lookup")
+                .required(true),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "date",
+            "The day to draw as YYYY-MM-DD, today when empty",
+        ))
+}
@@ -0,0 +1,242 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serenity::all::GuildId;
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::output::MAX_REPLY_LEN;
+use crate::sessions::{activity_sessions, format_duration, load_sessions, unix_now, Session};
+use crate::storage::{user_error, LogQuery, LogStore};
+
+pub fn run(options: &[ResolvedOption], guild: Option<GuildId>, store: &dyn LogStore) -> String {
+    let mut activity_name: Option<String> = None;
+    let mut member: Option<i64> = None;
+    let mut days: i64 = 7;
+    let mut min_minutes: i64 = 10;
+    let mut log_limit: i64 = 10;
+    for option in options {
+        match (option.name, &option.value) {
+            ("activity", ResolvedValue::String(value)) => activity_name = Some(value.to_string()),
+            ("user", ResolvedValue::User(user, _)) => member = Some(user.id.into()),
+            ("days", ResolvedValue::Integer(value)) => days = *value,
+            ("min_minutes", ResolvedValue::Integer(value)) => min_minutes = *value,
+            ("limit", ResolvedValue::Integer(value)) => log_limit = *value,
+            _ => {}
+        }
+    }
+
+    let to = unix_now();
+    let from = to - days * 24 * 60 * 60;
+    let query = LogQuery::new().guild(guild.map(i64::from));
+    let sessions = match load_sessions(store, from, to, query) {
+        Ok(sessions) => activity_sessions(&sessions),
+        Err(err) => return user_error(err),
+    };
+
+    let mut by_activity: HashMap<&str, Vec<&Session>> = HashMap::new();
+    for session in &sessions {
+        if let Some(name) = &activity_name {
+            if !session.activity.eq_ignore_ascii_case(name) {
+                continue;
+            }
+        }
+        by_activity
+            .entry(session.activity.as_str())
+            .or_default()
+            .push(session);
+    }
+
+    let mut groups: Vec<(&str, BTreeSet<i64>, i64)> = Vec::new();
+    for (activity, sessions) in by_activity {
+        for (users, secs) in overlaps(&sessions) {
+            if secs < min_minutes * 60 {
+                continue;
+            }
+            if let Some(member) = member {
+                if !users.contains(&member) {
+                    continue;
+                }
+            }
+            groups.push((activity, users, secs));
+        }
+    }
+    groups.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(b.0)));
+
+    let mut res_string = String::new();
+    for (activity, users, secs) in groups.into_iter().take(log_limit as usize) {
+        let line = format!(
+            "\n**{}**: {} played together for {}",
+            activity,
+            users
+                .iter()
+                .map(|user| format!("<@{}>", user))
+                .collect::<Vec<String>>()
+                .join(", "),
+            format_duration(secs)
+        );
+        if res_string.len() + line.len() > MAX_REPLY_LEN {
+            break;
+        }
+        res_string += &line;
+    }
+    if res_string.is_empty() {
+        res_string += "Nobody played together in that time window";
+    }
+    res_string
+}
+
+/// Sweeps over the sessions of one activity and sums up, for every set of two or more
+/// users, how long exactly that set was playing at the same time.
+fn overlaps(sessions: &[&Session]) -> HashMap<BTreeSet<i64>, i64> {
+    let mut events: Vec<(i64, bool, i64)> = Vec::new();
+    for session in sessions {
+        events.push((session.start, true, session.user_id));
+        events.push((session.end, false, session.user_id));
+    }
+    // Ends sort before starts at the same instant, so back-to-back sessions don't overlap
+    events.sort();
+
+    let mut result: HashMap<BTreeSet<i64>, i64> = HashMap::new();
+    let mut active: HashMap<i64, usize> = HashMap::new();
+    let mut previous: Option<i64> = None;
+    for (time, is_start, user) in events {
+        if let Some(previous) = previous {
+            if active.len() > 1 && time > previous {
+                *result.entry(active.keys().copied().collect()).or_default() += time - previous;
+            }
+        }
+        previous = Some(time);
+        if is_start {
+            *active.entry(user).or_default() += 1;
+        } else if let Some(count) = active.get_mut(&user) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&user);
+            }
+        }
+    }
+    result
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("together")
+        .description("Find who played the same game at the same time")
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::String,
+            "activity",
+            "Only look at this activity",
+        ))
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::User,
+            "user",
+            "Only show groups including this user",
+        ))
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "days",
+                "How many days to look back",
+            )
+            .min_int_value(1)
+            .max_int_value(90),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "min_minutes",
+                "Ignore groups that overlapped for less than this",
+            )
+            .min_int_value(1),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "limit",
+                "How much data to fetch",
+            )
+            .min_int_value(1)
+            .max_int_value(50),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serenity::all::GuildId;
+
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::{overlaps, run};
+    use crate::commands::testing::{command, store_with_aliases};
+    use crate::sessions::{unix_now, Session};
+    use crate::storage::Status::{Offline, Online};
+
+    const HOUR: i64 = 60 * 60;
+
+    #[test]
+    fn finds_players_of_the_same_game_by_its_canonical_name() {
+        let now = unix_now();
+        let store = store_with_aliases(
+            &[("CS2", "Counter-Strike 2")],
+            &[
+                (1, Some(5), Online, "Dota 2", now - 3 * HOUR),
+                (2, Some(5), Online, "Dota 2", now - 2 * HOUR),
+                (3, Some(5), Online, "CS2", now - 2 * HOUR),
+                (4, Some(5), Online, "Counter-Strike 2", now - 3 * HOUR / 2),
+                (1, Some(5), Online, "", now - HOUR),
+                (3, Some(5), Offline, "", now - HOUR),
+                (4, Some(5), Offline, "", now - HOUR),
+                (2, Some(5), Offline, "", now - HOUR / 2),
+                (5, Some(6), Online, "Dota 2", now - 3 * HOUR),
+            ],
+        );
+        let data = command("together", &[("days", json!(1))]);
+        let reply = run(&data.options(), Some(GuildId::new(5)), &store);
+        assert_eq!(
+            reply,
+            "\n**Dota 2**: <@1>, <@2> played together for 1h 0m\n\
+             **Counter-Strike 2**: <@3>, <@4> played together for 30m"
+        );
+
+        let data = command("together", &[("min_minutes", json!(45))]);
+        let reply = run(&data.options(), Some(GuildId::new(5)), &store);
+        assert_eq!(reply, "\n**Dota 2**: <@1>, <@2> played together for 1h 0m");
+    }
+
+    fn session(user_id: i64, start: i64, end: i64) -> Session {
+        Session {
+            user_id,
+            status: Online,
+            activity: "Dota 2".to_string(),
+            start,
+            end,
+        }
+    }
+
+    fn overlaps_of(sessions: &[Session]) -> HashMap<BTreeSet<i64>, i64> {
+        overlaps(&sessions.iter().collect::<Vec<&Session>>())
+    }
+
+    #[test]
+    fn touching_sessions_do_not_overlap() {
+        assert!(overlaps_of(&[session(1, 0, 100), session(2, 100, 200)]).is_empty());
+        assert_eq!(
+            overlaps_of(&[session(1, 0, 100), session(2, 99, 200)]),
+            HashMap::from([(BTreeSet::from([1, 2]), 1)])
+        );
+    }
+
+    #[test]
+    fn back_to_back_sessions_of_a_user_keep_the_group_together() {
+        let sessions = [
+            session(1, 0, 100),
+            session(1, 100, 200),
+            session(2, 50, 150),
+            session(3, 150, 300),
+        ];
+        assert_eq!(
+            overlaps_of(&sessions),
+            HashMap::from([(BTreeSet::from([1, 2]), 100), (BTreeSet::from([1, 3]), 50)])
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use serenity::all::{GuildId, UserId};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
+
+use crate::output::{parse_status, status_option};
+use crate::storage::{user_error, NewWatch, SettingsStore};
+use crate::watch::describe;
+
+const MAX_WATCHES: usize = 25;
+
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    subscriber: UserId,
+    settings: &dyn SettingsStore,
+) -> String {
+    let Some(guild) = guild else {
+        return "This command can only be used in a server".to_string();
+    };
+    let subscriber_id: i64 = subscriber.into();
+
+    match options.first() {
+        Some(ResolvedOption {
+            name: "add",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let mut watch = NewWatch {
+                guild_id: guild.into(),
+                subscriber_id,
+                target_id: 0,
+                activity: None,
+                status: parse_status(options),
+                channel_id: None,
+            };
+            for option in options {
+                match (option.name, &option.value) {
+                    ("user", ResolvedValue::User(user, _)) => watch.target_id = user.id.into(),
+                    ("activity", ResolvedValue::String(value)) => {
+                        watch.activity = Some(value.trim().to_string())
+                    }
+                    ("channel", ResolvedValue::Channel(channel)) => {
+                        watch.channel_id = Some(channel.id.into())
+                    }
+                    _ => {}
+                }
+            }
+            if watch.target_id == 0 {
+                return "Please provide a valid user".to_string();
+            }
+            match settings.is_opted_out(watch.target_id) {
+                Ok(true) => return "That user has opted out of being watched".to_string(),
+                Ok(false) => {}
+                Err(err) => return user_error(err),
+            }
+            match settings.get_watches_by_subscriber(watch.guild_id, subscriber_id) {
+                Ok(watches) if watches.len() >= MAX_WATCHES => {
+                    return format!(
+                        "You can't have more than {} watches, remove some first",
+                        MAX_WATCHES
+                    )
+                }
+                Ok(_) => {}
+                Err(err) => return user_error(err),
+            }
+
+            let target = watch.target_id;
+            let destination = match watch.channel_id {
+                Some(channel) => format!("in <#{}>", channel),
+                None => "in your DMs".to_string(),
+            };
+            match settings.add_watch(watch) {
+                Ok(()) => format!(
+                    "You will be notified {} when <@{}> changes accordingly",
+                    destination, target
+                ),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption { name: "list", .. }) => {
+            match settings.get_watches_by_subscriber(guild.into(), subscriber_id) {
+                Ok(watches) if watches.is_empty() => "You aren't watching anyone".to_string(),
+                Ok(watches) => watches
+                    .iter()
+                    .map(|watch| {
+                        format!(
+                            "#{}: when <@{}> {}{}",
+                            watch.id,
+                            watch.target_id,
+                            describe(watch),
+                            watch
+                                .channel_id
+                                .map(|channel| format!(", posted in <#{}>", channel))
+                                .unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                Err(err) => user_error(err),
+            }
+        }
+        Some(ResolvedOption {
+            name: "remove",
+            value: ResolvedValue::SubCommand(options),
+            ..
+        }) => {
+            let Some(ResolvedOption {
+                value: ResolvedValue::Integer(id),
+                ..
+            }) = options.first()
+            else {
+                return "Please provide a valid watch id".to_string();
+            };
+            match settings.delete_watch(*id as i32, subscriber_id) {
+                Ok(true) => format!("Watch #{} was removed", id),
+                Ok(false) => format!("You don't have a watch #{}", id),
+                Err(err) => user_error(err),
+            }
+        }
+        _ => "Unknown subcommand".to_string(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    CreateCommand::new("watch")
+        .description("Get notified when a user comes online or starts a game")
+        .dm_permission(false)
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Watch a user")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::User, "user", "The user to watch")
+                        .required(true),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "activity",
+                    "Only notify when they start this activity",
+                ))
+                .add_sub_option(
+                    status_option().description("Only notify when they switch to this status"),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::Channel,
+                    "channel",
+                    "Post notifications in this channel instead of your DMs",
+                )),
+        )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "List your watches",
+        ))
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a watch")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "id", "The watch id")
+                        .required(true),
+                ),
+        )
+}
@@ -1,60 +1,52 @@
-use diesel::prelude::*;
-use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::all::GuildId;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateInteractionResponseMessage};
 use serenity::model::application::{CommandOptionType, ResolvedOption, ResolvedValue};
 
-use crate::storage::establish_connection;
+use crate::output::{format_option, parse_format, render_logs};
+use crate::storage::{user_error, Distinct, LogQuery, LogStore};
 
-pub fn run(options: &[ResolvedOption]) -> String {
-    let mut res_string = String::new();
+pub fn run(
+    options: &[ResolvedOption],
+    guild: Option<GuildId>,
+    store: &dyn LogStore,
+) -> CreateInteractionResponseMessage {
+    let message = CreateInteractionResponseMessage::new();
     let mut log_limit: Option<i64> = None;
     let mut activity_name: String = String::new();
-    if let Some(ResolvedOption {
-        value: ResolvedValue::Integer(limit),
-        ..
-    }) = options.get(1)
-    {
-        log_limit = Some(limit.clone());
-    }
-    if let Some(ResolvedOption {
-        value: ResolvedValue::String(_activity),
-        ..
-    }) = options.get(0)
-    {
-        activity_name = String::from(*_activity);
-    }
-
-    use crate::schema::logs::dsl::*;
-    match &mut establish_connection() {
-        Ok(conn) => {
-            let limit = log_limit.unwrap_or(1) as i64;
-            let results = logs
-                .filter(activity.eq(activity_name))
-                .limit(limit)
-                .select(user_id)
-                .distinct()
-                .order(id.desc())
-                .load::<i64>(conn);
-
-            match results {
-                Ok(records) => {
-                    res_string.clear();
-
-                    res_string = records
-                        .iter()
-                        .map(|x| format!("<@{}>", x.to_string()))
-                        .collect::<Vec<String>>()
-                        .join("\n");
-                    if res_string.is_empty() {
-                        res_string += "Nothing was recorded in a database";
-                    }
-                }
-                Err(err) => return err.to_string(),
+    let mut fuzzy = false;
+    for option in options {
+        match (option.name, &option.value) {
+            ("activity", ResolvedValue::String(_activity)) => {
+                activity_name = String::from(*_activity)
             }
+            ("fuzzy", ResolvedValue::Boolean(value)) => fuzzy = *value,
+            ("limit", ResolvedValue::Integer(limit)) => log_limit = Some(*limit),
+            _ => {}
         }
-        Err(err) => return err.to_string(),
     }
 
-    return res_string;
+    // Most recent players first, each listed once
+    let query = LogQuery::new()
+        .guild(guild.map(i64::from))
+        .distinct(Distinct::User)
+        .limit(log_limit.unwrap_or(1));
+    let query = if fuzzy {
+        query.activity_like(&activity_name)
+    } else {
+        query.activity(&activity_name)
+    };
+    let records = match store.query(&query) {
+        Ok(records) => records,
+        Err(err) => return message.content(user_error(err)),
+    };
+
+    render_logs(&records, parse_format(options), "whoplayed", |records| {
+        records
+            .iter()
+            .map(|x| format!("<@{}>", x.user_id))
+            .collect::<Vec<String>>()
+            .join("\n")
+    })
 }
 
 pub fn register() -> CreateCommand {
@@ -65,6 +57,11 @@ pub fn register() -> CreateCommand {
             CreateCommandOption::new(CommandOptionType::String, "activity", "Activity type")
                 .required(true),
         )
+        .add_option(CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "fuzzy",
+            "Match activities containing the text, ignoring case",
+        ))
         .add_option(
             CreateCommandOption::new(
                 CommandOptionType::Integer,
@@ -74,4 +71,55 @@ pub fn register() -> CreateCommand {
             .min_int_value(1)
             .max_int_value(50),
         )
+        .add_option(format_option())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serenity::all::GuildId;
+
+    use super::run;
+    use crate::commands::testing::{command, content, store};
+    use crate::storage::Status::Online;
+
+    #[test]
+    fn lists_each_player_once_most_recent_first() {
+        let store = store(&[
+            (1, Some(5), Online, "Dota 2", 100),
+            (2, Some(5), Online, "Dota 2", 200),
+            (1, Some(5), Online, "Dota 2", 300),
+            (3, Some(6), Online, "Dota 2", 400),
+            (4, Some(5), Online, "CS2", 500),
+        ]);
+        let data = command(
+            "whoplayed",
+            &[("activity", json!("Dota 2")), ("limit", json!(5))],
+        );
+        let reply = content(&run(&data.options(), Some(GuildId::new(5)), &store));
+        assert_eq!(reply, "<@1>\n<@2>");
+
+        let data = command("whoplayed", &[("activity", json!("Dota 2"))]);
+        let reply = content(&run(&data.options(), None, &store));
+        assert_eq!(reply, "<@3>");
+    }
+
+    #[test]
+    fn fuzzy_matches_part_of_the_name() {
+        let store = store(&[
+            (1, None, Online, "Dota 2", 100),
+            (2, None, Online, "Dota Underlords", 200),
+            (3, None, Online, "CS2", 300),
+        ]);
+        let data = command(
+            "whoplayed",
+            &[
+                ("activity", json!("dota")),
+                ("fuzzy", json!(true)),
+                ("limit", json!(5)),
+            ],
+        );
+        let reply = content(&run(&data.options(), None, &store));
+        assert_eq!(reply, "<@2>\n<@1>");
+    }
 }
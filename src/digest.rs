@@ -0,0 +1,152 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use chrono::TimeZone;
+use chrono_tz::Tz;
+use serenity::all::{CreateEmbed, CreateEmbedFooter};
+
+use crate::sessions::{format_duration, load_totals};
+use crate::storage::{LogStore, StorageError};
+
+const WEEK_SECS: i64 = 7 * 24 * 60 * 60;
+const TOP: usize = 5;
+const MAX_NEW: usize = 10;
+
+/// Online time per user and playtime per activity over one week.
+struct Week {
+    online: HashMap<i64, i64>,
+    playtime: HashMap<String, i64>,
+}
+
+impl Week {
+    fn load(store: &dyn LogStore, guild: i64, from: i64, to: i64) -> Result<Self, StorageError> {
+        let totals = load_totals(store, from, to, Some(guild), None)?;
+        Ok(Week {
+            online: totals.online(),
+            playtime: totals.playtime(),
+        })
+    }
+
+    fn total_online(&self) -> i64 {
+        self.online.values().sum()
+    }
+
+    fn total_playtime(&self) -> i64 {
+        self.playtime.values().sum()
+    }
+}
+
+fn change(current: i64, previous: i64) -> String {
+    if previous == 0 {
+        return if current == 0 { "±0%" } else { "new" }.to_string();
+    }
+    let percent = (current - previous) as f64 * 100.0 / previous as f64;
+    format!("{:+.0}%", percent)
+}
+
+fn or_nothing(lines: Vec<String>) -> String {
+    if lines.is_empty() {
+        return "Nothing this week".to_string();
+    }
+    lines.join("\n")
+}
+
+/// Builds the summary of the 7 days before `now`, compared to the 7 days before that.
+pub fn build(
+    store: &dyn LogStore,
+    guild: i64,
+    tz: Tz,
+    now: i64,
+) -> Result<CreateEmbed, StorageError> {
+    let week_start = now - WEEK_SECS;
+    let current = Week::load(store, guild, week_start, now)?;
+    let previous = Week::load(store, guild, week_start - WEEK_SECS, week_start)?;
+
+    let mut games: Vec<(&String, &i64)> = current.playtime.iter().collect();
+    games.sort_by_key(|(name, secs)| (Reverse(**secs), *name));
+    let top_games = games
+        .iter()
+        .take(TOP)
+        .enumerate()
+        .map(|(index, (name, secs))| {
+            format!(
+                "{}. **{}**: {} ({})",
+                index + 1,
+                name,
+                format_duration(**secs),
+                change(**secs, previous.playtime.get(*name).copied().unwrap_or(0))
+            )
+        })
+        .collect();
+
+    let mut members: Vec<(&i64, &i64)> = current.online.iter().collect();
+    members.sort_by_key(|(user, secs)| (Reverse(**secs), **user));
+    let top_members = members
+        .iter()
+        .take(TOP)
+        .enumerate()
+        .map(|(index, (user, secs))| {
+            format!(
+                "{}. <@{}>: {} ({})",
+                index + 1,
+                user,
+                format_duration(**secs),
+                change(**secs, previous.online.get(*user).copied().unwrap_or(0))
+            )
+        })
+        .collect();
+
+    let new_activities = store.new_activities(guild, week_start)?;
+    let mut new_games: Vec<String> = new_activities
+        .iter()
+        .take(MAX_NEW)
+        .map(|(name, first_seen)| format!("**{}**, first seen <t:{}:R>", name, first_seen))
+        .collect();
+    if new_activities.len() > MAX_NEW {
+        new_games.push(format!("And {} more", new_activities.len() - MAX_NEW));
+    }
+
+    let totals = [
+        format!(
+            "Online time: {} ({})",
+            format_duration(current.total_online()),
+            change(current.total_online(), previous.total_online())
+        ),
+        format!(
+            "Playtime: {} ({})",
+            format_duration(current.total_playtime()),
+            change(current.total_playtime(), previous.total_playtime())
+        ),
+        format!(
+            "Active members: {} ({})",
+            current.online.len(),
+            change(current.online.len() as i64, previous.online.len() as i64)
+        ),
+        format!(
+            "Games played: {} ({})",
+            current.playtime.len(),
+            change(
+                current.playtime.len() as i64,
+                previous.playtime.len() as i64
+            )
+        ),
+    ];
+
+    let date = |time: i64| {
+        tz.timestamp_opt(time, 0)
+            .single()
+            .map(|local| local.format("%b %-d").to_string())
+            .unwrap_or_default()
+    };
+    Ok(CreateEmbed::new()
+        .title(format!(
+            "Weekly digest: {} to {}",
+            date(week_start),
+            date(now)
+        ))
+        .field("Top games", or_nothing(top_games), false)
+        .field("Most active members", or_nothing(top_members), false)
+        .field("New games", or_nothing(new_games), false)
+        .field("Compared to last week", totals.join("\n"), false)
+        .footer(CreateEmbedFooter::new(format!("Time zone: {}", tz))))
+}
@@ -14,22 +14,22 @@ pub enum Types {
 
 impl Types {
     pub fn supports(&self, op: BinaryOperations) -> bool {
-        match (self, op) {
+        matches!(
+            (self, op),
             (Types::Integer, BinaryOperations::Add)
-            | (Types::Integer, BinaryOperations::Subtract)
-            | (Types::Integer, BinaryOperations::Divide)
-            | (Types::Integer, BinaryOperations::Multiply)
-            | (Types::UnsingedInteger, BinaryOperations::Add)
-            | (Types::UnsingedInteger, BinaryOperations::Subtract)
-            | (Types::UnsingedInteger, BinaryOperations::Divide)
-            | (Types::UnsingedInteger, BinaryOperations::Multiply)
-            | (Types::Float, BinaryOperations::Add)
-            | (Types::Float, BinaryOperations::Subtract)
-            | (Types::Float, BinaryOperations::Divide)
-            | (Types::Float, BinaryOperations::Multiply)
-            | (Types::String, BinaryOperations::Add) => true,
-            _ => false,
-        }
+                | (Types::Integer, BinaryOperations::Subtract)
+                | (Types::Integer, BinaryOperations::Divide)
+                | (Types::Integer, BinaryOperations::Multiply)
+                | (Types::UnsingedInteger, BinaryOperations::Add)
+                | (Types::UnsingedInteger, BinaryOperations::Subtract)
+                | (Types::UnsingedInteger, BinaryOperations::Divide)
+                | (Types::UnsingedInteger, BinaryOperations::Multiply)
+                | (Types::Float, BinaryOperations::Add)
+                | (Types::Float, BinaryOperations::Subtract)
+                | (Types::Float, BinaryOperations::Divide)
+                | (Types::Float, BinaryOperations::Multiply)
+                | (Types::String, BinaryOperations::Add)
+        )
     }
 }
 
@@ -190,10 +190,10 @@ pub struct IntegerExpression {
 
 impl Expression for FloatExpression {
     fn get_description(&self) -> AbstractExpressionDescription {
-        return AbstractExpressionDescription::Float(AbstractValue::new(
+        AbstractExpressionDescription::Float(AbstractValue::new(
             Box::new(self.value.to_be_bytes().to_vec()),
             Types::Float,
-        ));
+        ))
     }
 
     fn get_memory_allocation_info(&self) -> MemoryAllocationInfo {
@@ -229,10 +229,10 @@ pub struct StringExpression {
 }
 impl Expression for StringExpression {
     fn get_description(&self) -> AbstractExpressionDescription {
-        return AbstractExpressionDescription::LiteralString(AbstractValue::new(
+        AbstractExpressionDescription::LiteralString(AbstractValue::new(
             Box::new(self.value.clone().into_bytes()),
             Types::String,
-        ));
+        ))
     }
 
     fn get_memory_allocation_info(&self) -> MemoryAllocationInfo {
@@ -333,11 +333,11 @@ impl Expression for FunctionCallExpression {
             .iter()
             .map(|v| Box::new(v.get_description()))
             .collect();
-        AbstractExpressionDescription::FunctionCall {
-            0: Box::new(self.identifier.get_description()),
-            1: params.len(),
-            2: params,
-        }
+        AbstractExpressionDescription::FunctionCall(
+            Box::new(self.identifier.get_description()),
+            params.len(),
+            params,
+        )
     }
 }
 
@@ -348,13 +348,12 @@ pub struct BlockStatement {
 }
 impl Statement for BlockStatement {
     fn get_description(&self) -> AbstractStatementDescription {
-        AbstractStatementDescription::Block {
-            0: self
-                .body
+        AbstractStatementDescription::Block(
+            self.body
                 .iter()
                 .map(|v| Box::new(v.get_description()))
                 .collect(),
-        }
+        )
     }
 
     fn get_memory_allocation_info(&self) -> MemoryAllocationInfo {
@@ -376,9 +375,7 @@ impl Statement for ExpressionStatement {
     }
 
     fn get_description(&self) -> AbstractStatementDescription {
-        AbstractStatementDescription::Expression {
-            0: Box::new(self.expression.get_description()),
-        }
+        AbstractStatementDescription::Expression(Box::new(self.expression.get_description()))
     }
 }
 
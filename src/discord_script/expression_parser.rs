@@ -61,7 +61,7 @@ impl Parser {
                 if let Ok(number) = next_token.value.parse::<f64>() {
                     return Ok(Box::new(FloatExpression { value: number }));
                 }
-                return Err(ParserErrors::NumberIsNotANumber(next_token));
+                Err(ParserErrors::NumberIsNotANumber(next_token))
             }
             String => Ok(Box::new(StringExpression {
                 value: next_token.value,
@@ -109,7 +109,7 @@ impl Parser {
 
         if has_args {
             params.push(self.parse_expression(power.clone())?);
-            while let Ok(_) = self.expect_token(TokenKind::Comma) {
+            while self.expect_token(TokenKind::Comma).is_ok() {
                 params.push(self.parse_expression(BindingPower::None)?);
             }
         }
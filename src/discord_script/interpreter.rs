@@ -15,6 +15,8 @@ pub struct CustomType {
     identifier: String,
 }
 
+// Variables have always compared by value, then depth, while `Ord` only looks at the depth
+#[allow(clippy::derive_ord_xor_partial_ord)]
 #[derive(PartialEq, Eq, PartialOrd, Debug, Clone)]
 pub struct Variable {
     pub value: AbstractValue,
@@ -35,13 +37,14 @@ pub struct Function {
 }
 
 pub type SystemFunctionExecutor<'a> = Box<dyn Fn(String, Vec<AbstractValue>) -> Option<Types> + 'a>;
+pub type NullExpressionOut<'a> = Box<dyn FnMut(&AbstractValue) + 'a>;
 
 pub struct Interpreter<'a> {
     pub vars: HashMap<String, Vec<Variable>>,
     pub system_functions: HashMap<String, Function>,
     pub system_function_executor: Option<SystemFunctionExecutor<'a>>,
     pub current_depth: usize,
-    pub null_expression_out: Option<Box<dyn FnMut(&AbstractValue) + 'a>>,
+    pub null_expression_out: Option<NullExpressionOut<'a>>,
     pub on_error: Option<Box<dyn Fn(String) + 'a>>,
 }
 
@@ -62,6 +65,12 @@ impl Display for InterpreterErrors {
 }
 impl Error for InterpreterErrors {}
 
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interpreter<'_> {
     pub fn new() -> Self {
         let mut res = Self {
@@ -277,7 +286,7 @@ impl Interpreter<'_> {
                         }
 
                         (executor)(identifier, args);
-                        return Ok(AbstractValue::new(Box::new(vec![]), Types::Void));
+                        return Ok(AbstractValue::new(Box::default(), Types::Void));
                     }
                 }
                 Err(Box::new(InterpreterErrors::Unimplemented))
@@ -71,11 +71,11 @@ impl Parser {
                     self.current_token().clone(),
                 ))?;
 
-        return match handler_type {
+        match handler_type {
             NullDenotationHandlerTypes::Default => self.parse_primary_expression(),
             NullDenotationHandlerTypes::Groupping => self.parse_groupping_expression(),
             NullDenotationHandlerTypes::Unary => self.parse_unary_expression(),
-        };
+        }
     }
 
     fn try_parse_left_denotation(
@@ -88,7 +88,7 @@ impl Parser {
             ParserErrors::UnexpectedExpressionType(self.current_token().clone()),
         )?;
 
-        return match function_type {
+        match function_type {
             LeftDenotationHandlerTypes::Default => {
                 self.parse_binary_expression(left, new_power.clone())
             }
@@ -98,7 +98,7 @@ impl Parser {
             LeftDenotationHandlerTypes::FunctionCall => {
                 self.parse_function_call(left, new_power.clone())
             }
-        };
+        }
     }
 
     pub(super) fn parse_expression(
@@ -140,7 +140,7 @@ impl Parser {
         let expression = self.parse_expression(BindingPower::None)?;
         self.expect_token(TokenKind::SemiColon)?;
 
-        return Ok(Box::new(ExpressionStatement { expression }));
+        Ok(Box::new(ExpressionStatement { expression }))
     }
 
     fn has_tokens(&self) -> bool {
@@ -159,7 +159,7 @@ impl Parser {
     }
 
     pub(super) fn current_token(&self) -> &Token {
-        return &self.tokens.front().unwrap();
+        self.tokens.front().unwrap()
     }
 
     pub(super) fn next_token(&mut self) -> Result<Token, ParserErrors> {
@@ -189,7 +189,7 @@ impl Parser {
         }
         let token = self.next_token()?;
 
-        return Ok(token);
+        Ok(token)
     }
 
     pub(self) fn create_lookup_table(&mut self) {
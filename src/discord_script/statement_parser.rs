@@ -41,11 +41,11 @@ impl Parser {
 
         self.expect_token(TokenKind::SemiColon)?;
 
-        return Ok(Box::new(VariableStatement {
+        Ok(Box::new(VariableStatement {
             is_const,
             variable_name: name_token.value,
             explicit_type: explicit_type_val,
             assignment: expr,
-        }));
+        }))
     }
 }
@@ -11,7 +11,7 @@ pub struct Lexer {
     patterns: Vec<RegexPattern>,
 }
 
-pub type HandlerType = dyn Fn(&Regex, &String, usize, usize) -> (usize, Option<Token>);
+pub type HandlerType = dyn Fn(&Regex, &str, usize, usize) -> (usize, Option<Token>);
 
 pub struct RegexPattern {
     regex: regex::Regex,
@@ -38,16 +38,16 @@ impl Display for TokenizerError {
 impl std::error::Error for TokenizerError {}
 
 fn default_handler(kind: TokenKind, value: &'static str) -> Box<HandlerType> {
-    let res = move |_: &Regex, _: &String, line: usize, pos: usize| -> (usize, Option<Token>) {
-        return (
+    let res = move |_: &Regex, _: &str, line: usize, pos: usize| -> (usize, Option<Token>) {
+        (
             value.len(),
             Some(Token::new(kind.clone(), String::from(value), line, pos)),
-        );
+        )
     };
     Box::new(res)
 }
 
-fn skip_handler(regex: &Regex, remainder: &String, _: usize, _: usize) -> (usize, Option<Token>) {
+fn skip_handler(regex: &Regex, remainder: &str, _: usize, _: usize) -> (usize, Option<Token>) {
     if let Some(pat) = regex.find(remainder) {
         return (pat.end(), None);
     }
@@ -56,7 +56,7 @@ fn skip_handler(regex: &Regex, remainder: &String, _: usize, _: usize) -> (usize
 
 fn string_handler(
     regex: &Regex,
-    remainder: &String,
+    remainder: &str,
     line: usize,
     pos: usize,
 ) -> (usize, Option<Token>) {
@@ -77,7 +77,7 @@ fn string_handler(
 
 fn number_handler(
     regex: &Regex,
-    remainder: &String,
+    remainder: &str,
     line: usize,
     pos: usize,
 ) -> (usize, Option<Token>) {
@@ -98,7 +98,7 @@ fn number_handler(
 
 fn symbol_handler(
     regex: &Regex,
-    remainder: &String,
+    remainder: &str,
     line: usize,
     pos: usize,
 ) -> (usize, Option<Token>) {
@@ -0,0 +1,132 @@
+//! Streams logs into a gzip compressed file, a page of rows at a time, so exporting the
+//! whole table never holds more than one page in memory.
+
+use std::io::{self, Write};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::output::{csv_row, CSV_HEADER};
+use crate::sessions::day_bounds;
+use crate::storage::{user_error, Cursor, LogQuery, LogStore};
+
+const PAGE_SIZE: i64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    pub fn file_name(self) -> String {
+        format!("logs.{}.gz", self.extension())
+    }
+}
+
+/// Turns inclusive `YYYY-MM-DD` dates into the `[from, to)` unix time range of a query.
+pub fn parse_date_range(
+    from: Option<&str>,
+    to: Option<&str>,
+    tz: Tz,
+) -> Result<(Option<i64>, Option<i64>), String> {
+    let bounds = |date: &str| {
+        let day = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Please provide the date {} as YYYY-MM-DD", date))?;
+        day_bounds(day, tz).ok_or(format!("{} doesn't exist in the time zone {}", day, tz))
+    };
+    let from = from.map(bounds).transpose()?.map(|(start, _)| start);
+    let to = to.map(bounds).transpose()?.map(|(_, end)| end);
+    if let (Some(from), Some(to)) = (from, to) {
+        if from >= to {
+            return Err("The start date must not be after the end date".to_string());
+        }
+    }
+    Ok((from, to))
+}
+
+/// Writes every log matching `query` to `writer` as a gzip compressed file, oldest first and
+/// leaving out users who opted out. Returns the number of exported rows.
+pub fn export_logs<W: Write>(
+    store: &dyn LogStore,
+    writer: W,
+    query: &LogQuery,
+    format: ExportFormat,
+) -> Result<usize, String> {
+    let query = query
+        .clone()
+        .exclude_users(&store.opted_out().map_err(user_error)?)
+        .oldest_first();
+
+    let mut encoder = GzEncoder::new(writer, Compression::default());
+    let count = write_rows(store, &mut encoder, &query, format).map_err(|err| err.to_string())?;
+    encoder.finish().map_err(|err| err.to_string())?;
+    Ok(count)
+}
+
+fn write_rows<W: Write>(
+    store: &dyn LogStore,
+    out: &mut W,
+    query: &LogQuery,
+    format: ExportFormat,
+) -> io::Result<usize> {
+    match format {
+        ExportFormat::Csv => writeln!(out, "{}", CSV_HEADER)?,
+        ExportFormat::Json => write!(out, "[")?,
+        ExportFormat::Ndjson => {}
+    }
+
+    let mut count = 0;
+    let mut cursor = None;
+    loop {
+        let page = store
+            .query(&query.clone().after(cursor).limit(PAGE_SIZE))
+            .map_err(|err| io::Error::other(user_error(err)))?;
+        for record in &page {
+            match format {
+                ExportFormat::Csv => writeln!(out, "{}", csv_row(record))?,
+                ExportFormat::Json => {
+                    if count > 0 {
+                        write!(out, ",")?;
+                    }
+                    write!(out, "\n  ")?;
+                    serde_json::to_writer(&mut *out, record)?;
+                }
+                ExportFormat::Ndjson => {
+                    serde_json::to_writer(&mut *out, record)?;
+                    writeln!(out)?;
+                }
+            }
+            count += 1;
+        }
+        match page.last() {
+            Some(last) if page.len() as i64 == PAGE_SIZE => cursor = Some(Cursor::after(last)),
+            _ => break,
+        }
+    }
+
+    if format == ExportFormat::Json {
+        writeln!(out, "\n]")?;
+    }
+    Ok(count)
+}
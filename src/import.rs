@@ -0,0 +1,458 @@
+//! Backfills `logs` from files written by other bots. Every row is mapped onto a `NewLog`
+//! through a column mapping, validated, checked for duplicates and inserted in batches.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::Read;
+
+use chrono::{DateTime, NaiveDateTime};
+use flate2::read::GzDecoder;
+use serde_json::Value;
+
+use crate::export::ExportFormat;
+use crate::sessions::unix_now;
+use crate::storage::{LogQuery, LogStore, NewLog, Status};
+
+/// Rows inserted per transaction.
+const BATCH_SIZE: usize = 500;
+/// Only the first problems are listed in the report, the rest are just counted.
+const MAX_REPORTED_ERRORS: usize = 20;
+
+pub type Row = HashMap<String, String>;
+
+/// Names of the input columns holding each `NewLog` field.
+#[derive(Clone, Debug)]
+pub struct Mapping {
+    pub user_id: String,
+    pub status: String,
+    pub activity: String,
+    pub unix_time: String,
+    /// Optional, logs without it are kept with no guild.
+    pub guild_id: String,
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Self {
+            user_id: "user_id".to_string(),
+            status: "status".to_string(),
+            activity: "activity".to_string(),
+            unix_time: "unix_time".to_string(),
+            guild_id: "guild_id".to_string(),
+        }
+    }
+}
+
+impl Mapping {
+    /// Parses `field=column` pairs separated by commas, such as `user_id=member,unix_time=ts`.
+    /// Fields that aren't mentioned keep their own name as the column name.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut mapping = Self::default();
+        for pair in value.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let Some((field, column)) = pair.split_once('=') else {
+                return Err(format!("Expected field=column, got {}", pair));
+            };
+            let column = column.trim().to_string();
+            match field.trim() {
+                "user_id" => mapping.user_id = column,
+                "status" => mapping.status = column,
+                "activity" => mapping.activity = column,
+                "unix_time" => mapping.unix_time = column,
+                "guild_id" => mapping.guild_id = column,
+                field => return Err(format!("Unknown field {}", field)),
+            }
+        }
+        Ok(mapping)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub rows: usize,
+    pub invalid: usize,
+    pub duplicates: usize,
+    pub inserted: usize,
+    pub errors: Vec<String>,
+}
+
+impl Display for ImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Rows read:  {}", self.rows)?;
+        writeln!(f, "Invalid:    {}", self.invalid)?;
+        writeln!(f, "Duplicates: {}", self.duplicates)?;
+        if self.dry_run {
+            write!(
+                f,
+                "Would insert: {} (dry run, nothing was written)",
+                self.inserted
+            )?;
+        } else {
+            write!(f, "Inserted:   {}", self.inserted)?;
+        }
+        for error in &self.errors {
+            write!(f, "\n  {}", error)?;
+        }
+        if self.invalid > self.errors.len() {
+            write!(f, "\n  and {} more", self.invalid - self.errors.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Guesses the format from the file name, ignoring a `.gz` suffix.
+pub fn detect_format(path: &str) -> Option<ExportFormat> {
+    let path = path.strip_suffix(".gz").unwrap_or(path);
+    ExportFormat::parse(path.rsplit_once('.')?.1)
+}
+
+/// Reads every row of the file at `path`, decompressing it when it ends with `.gz`.
+pub fn read_rows(path: &str, format: ExportFormat) -> Result<Vec<Row>, String> {
+    let mut file = File::open(path).map_err(|err| format!("Cannot open {}: {}", path, err))?;
+    let mut text = String::new();
+    let read = if path.ends_with(".gz") {
+        GzDecoder::new(file).read_to_string(&mut text)
+    } else {
+        file.read_to_string(&mut text)
+    };
+    read.map_err(|err| format!("Cannot read {}: {}", path, err))?;
+
+    match format {
+        ExportFormat::Csv => parse_csv(&text),
+        ExportFormat::Json => match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Array(values)) => values.into_iter().map(json_row).collect(),
+            Ok(_) => Err("Expected a JSON array of objects".to_string()),
+            Err(err) => Err(err.to_string()),
+        },
+        ExportFormat::Ndjson => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<Value>(line)
+                    .map_err(|err| err.to_string())
+                    .and_then(json_row)
+            })
+            .collect(),
+    }
+}
+
+fn json_row(value: Value) -> Result<Row, String> {
+    let Value::Object(object) = value else {
+        return Err("Expected every JSON row to be an object".to_string());
+    };
+    Ok(object
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            Value::String(value) => Some((key, value)),
+            Value::Number(value) => Some((key, value.to_string())),
+            Value::Bool(value) => Some((key, value.to_string())),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Parses CSV with a header line, supporting quoted fields with commas, doubled quotes
+/// and line breaks in them.
+fn parse_csv(text: &str) -> Result<Vec<Row>, String> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted field at the end of the file".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|record| !(record.len() == 1 && record[0].trim().is_empty()));
+
+    let mut records = records.into_iter();
+    let Some(header) = records.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = header.iter().map(|name| name.trim().to_string()).collect();
+    Ok(records
+        .map(|record| header.iter().cloned().zip(record).collect())
+        .collect())
+}
+
+/// Accepts unix seconds, RFC 3339 or `YYYY-MM-DD HH:MM:SS` in UTC.
+fn parse_time(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(time) = value.parse::<i64>() {
+        return Some(time);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.timestamp());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|time| time.and_utc().timestamp())
+}
+
+fn to_log(row: &Row, mapping: &Mapping, now: i64) -> Result<NewLog, String> {
+    let field = |column: &String| {
+        row.get(column)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+
+    let user_id = field(&mapping.user_id)
+        .ok_or(format!("missing {}", mapping.user_id))?
+        .parse::<i64>()
+        .ok()
+        .filter(|user_id| *user_id > 0)
+        .ok_or("user id is not a valid Discord id")?;
+    let status = field(&mapping.status)
+        .ok_or(format!("missing {}", mapping.status))?
+        .parse::<Status>()?;
+    let time = field(&mapping.unix_time).ok_or(format!("missing {}", mapping.unix_time))?;
+    let unix_time = parse_time(time).ok_or(format!("invalid time {}", time))?;
+    if unix_time <= 0 || unix_time > now {
+        return Err(format!("time {} is out of range", time));
+    }
+    let guild_id = match field(&mapping.guild_id) {
+        Some(guild) => Some(
+            guild
+                .parse::<i64>()
+                .ok()
+                .filter(|guild| *guild > 0)
+                .ok_or("guild id is not a valid Discord id")?,
+        ),
+        None => None,
+    };
+
+    Ok(NewLog {
+        user_id,
+        status,
+        activity: field(&mapping.activity).unwrap_or_default().to_string(),
+        unix_time,
+        guild_id,
+    })
+}
+
+/// Validates `rows` and inserts the ones not recorded yet, a transaction per batch.
+/// Rows are duplicates when a log with the same user, time and status already exists,
+/// in the database or earlier in the file. With `dry_run` nothing is written.
+pub fn import_logs(
+    store: &dyn LogStore,
+    rows: &[Row],
+    mapping: &Mapping,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    let now = unix_now();
+    let mut report = ImportReport {
+        dry_run,
+        rows: rows.len(),
+        ..Default::default()
+    };
+    let mut seen: HashSet<(i64, i64, Status)> = HashSet::new();
+
+    for (batch_index, batch) in rows.chunks(BATCH_SIZE).enumerate() {
+        let mut records = Vec::with_capacity(batch.len());
+        for (index, row) in batch.iter().enumerate() {
+            match to_log(row, mapping, now) {
+                Ok(record) => records.push(record),
+                Err(err) => {
+                    report.invalid += 1;
+                    if report.errors.len() < MAX_REPORTED_ERRORS {
+                        let number = batch_index * BATCH_SIZE + index + 1;
+                        report.errors.push(format!("row {}: {}", number, err));
+                    }
+                }
+            }
+        }
+        if records.is_empty() {
+            continue;
+        }
+
+        let valid = records.len();
+        let users: Vec<i64> = records
+            .iter()
+            .map(|record| record.user_id)
+            .collect::<HashSet<i64>>()
+            .into_iter()
+            .collect();
+        let from = records.iter().map(|record| record.unix_time).min().unwrap();
+        let to = records.iter().map(|record| record.unix_time).max().unwrap();
+        let query = LogQuery::new()
+            .users(&users)
+            .between(Some(from), Some(to + 1));
+        seen.extend(
+            store
+                .query(&query)
+                .map_err(|err| err.to_string())?
+                .into_iter()
+                .map(|record| (record.user_id, record.unix_time, record.status)),
+        );
+
+        records.retain(|record| seen.insert((record.user_id, record.unix_time, record.status)));
+        report.duplicates += valid - records.len();
+        if !dry_run && !records.is_empty() {
+            store.insert_many(&records).map_err(|err| err.to_string())?;
+        }
+        report.inserted += records.len();
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import_logs, parse_csv, parse_time, Mapping, Row};
+    use crate::commands::testing::store;
+    use crate::storage::Status::{Idle, Online};
+    use crate::storage::{LogQuery, LogStore};
+
+    fn row(pairs: &[(&str, &str)]) -> Row {
+        pairs
+            .iter()
+            .map(|(column, value)| (column.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_quoted_csv_fields() {
+        let rows = parse_csv(
+            "user_id,activity,status\r\n\
+             1,\"Dota 2, ranked\",online\r\n\
+             \r\n\
+             2,\"The \"\"Big\"\" Game\",idle\n\
+             3,\"Two\nlines\",dnd",
+        )
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                row(&[
+                    ("user_id", "1"),
+                    ("activity", "Dota 2, ranked"),
+                    ("status", "online")
+                ]),
+                row(&[
+                    ("user_id", "2"),
+                    ("activity", "The \"Big\" Game"),
+                    ("status", "idle")
+                ]),
+                row(&[
+                    ("user_id", "3"),
+                    ("activity", "Two\nlines"),
+                    ("status", "dnd")
+                ]),
+            ]
+        );
+
+        assert!(parse_csv("user_id,activity\n1,\"Dota 2").is_err());
+    }
+
+    #[test]
+    fn parses_every_accepted_time_format() {
+        assert_eq!(parse_time(" 1760000000 "), Some(1760000000));
+        assert_eq!(parse_time("2025-10-09T08:53:20Z"), Some(1760000000));
+        assert_eq!(parse_time("2025-10-09T11:53:20+03:00"), Some(1760000000));
+        assert_eq!(parse_time("2025-10-09 08:53:20"), Some(1760000000));
+        assert_eq!(parse_time("2025-10-09"), None);
+        assert_eq!(parse_time("yesterday"), None);
+    }
+
+    #[test]
+    fn reports_rows_with_an_unknown_status_or_user() {
+        let rows = [
+            row(&[
+                ("user_id", "1"),
+                ("status", "away"),
+                ("unix_time", "1760000000"),
+            ]),
+            row(&[("status", "online"), ("unix_time", "1760000000")]),
+            row(&[
+                ("user_id", "someone"),
+                ("status", "online"),
+                ("unix_time", "1760000000"),
+            ]),
+            row(&[
+                ("user_id", "0"),
+                ("status", "online"),
+                ("unix_time", "1760000000"),
+            ]),
+            row(&[
+                ("user_id", "2"),
+                ("status", "online"),
+                ("unix_time", "1760000000"),
+            ]),
+        ];
+        let store = store(&[]);
+        let report = import_logs(&store, &rows, &Mapping::default(), false).unwrap();
+        assert_eq!((report.invalid, report.inserted), (4, 1));
+        assert_eq!(
+            report.errors,
+            vec![
+                "row 1: unknown status away",
+                "row 2: missing user_id",
+                "row 3: user id is not a valid Discord id",
+                "row 4: user id is not a valid Discord id",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_logs_already_recorded_or_repeated() {
+        let store = store(&[(1, Some(5), Online, "Dota 2", 1760000000)]);
+        let rows = [
+            // Same user, time and status as the stored log, whatever the rest
+            row(&[
+                ("user_id", "1"),
+                ("status", "online"),
+                ("unix_time", "1760000000"),
+            ]),
+            row(&[
+                ("user_id", "1"),
+                ("status", "idle"),
+                ("unix_time", "1760000000"),
+            ]),
+            row(&[
+                ("user_id", "2"),
+                ("status", "online"),
+                ("unix_time", "1760000000"),
+            ]),
+            row(&[
+                ("user_id", "2"),
+                ("status", "online"),
+                ("unix_time", "2025-10-09 08:53:20"),
+            ]),
+        ];
+
+        let report = import_logs(&store, &rows, &Mapping::default(), true).unwrap();
+        assert_eq!((report.duplicates, report.inserted), (2, 2));
+        assert_eq!(store.query(&LogQuery::new()).unwrap().len(), 1);
+
+        let report = import_logs(&store, &rows, &Mapping::default(), false).unwrap();
+        assert_eq!((report.duplicates, report.inserted), (2, 2));
+        let mut logs: Vec<_> = store
+            .query(&LogQuery::new())
+            .unwrap()
+            .into_iter()
+            .map(|log| (log.user_id, log.status))
+            .collect();
+        logs.sort_by_key(|(user, status)| (*user, status.as_str()));
+        assert_eq!(logs, vec![(1, Idle), (1, Online), (2, Online)]);
+    }
+}
@@ -1,8 +1,28 @@
 #![cfg_attr(not(debug_assertions), deny(warnings))]
+pub mod alerts;
+pub mod aliases;
+pub mod audit;
+#[cfg(not(feature = "postgres"))]
+pub mod backup;
+pub mod board;
+pub mod cli;
 pub mod commands;
+pub mod digest;
 pub mod discord_script;
+pub mod export;
+pub mod import;
+pub mod migrate;
+#[cfg(feature = "postgres")]
+pub mod migrate_data;
+pub mod output;
+pub mod rate_limit;
+pub mod render;
+pub mod scheduler;
 pub mod schema;
+pub mod sessions;
 pub mod storage;
+pub mod uptime;
+pub mod watch;
 
 use dotenv::dotenv;
 use serenity::all::CreateInteractionResponse;
@@ -15,17 +35,60 @@ use serenity::all::*;
 use serenity::async_trait;
 
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
+use self::audit::Outcome;
+use self::board::SharedNowPlaying;
+use self::rate_limit::RateLimiter;
+use self::sessions::unix_now;
 use self::storage::*;
+use self::watch::{PresenceState, SharedPresences};
 
-struct Handler;
+fn text(content: String) -> CreateInteractionResponseMessage {
+    CreateInteractionResponseMessage::new().content(content)
+}
+
+/// Acknowledges `command` at once and replies with the result of `job` once it finished on
+/// a blocking thread, for commands that take longer than the 3 seconds Discord waits.
+async fn run_deferred(
+    ctx: &Context,
+    command: &CommandInteraction,
+    job: impl FnOnce() -> EditInteractionResponse + Send + 'static,
+) {
+    let defer =
+        CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new().ephemeral(true));
+    if let Err(why) = command.create_response(&ctx.http, defer).await {
+        println!("Cannot respond to slash command: {why}");
+        return;
+    }
+    let reply = tokio::task::spawn_blocking(job)
+        .await
+        .unwrap_or_else(|err| {
+            println!("Error while running /{}: {}", command.data.name, err);
+            EditInteractionResponse::new().content("Something went wrong, try again later")
+        });
+    if let Err(why) = command.edit_response(&ctx.http, reply).await {
+        println!("Cannot respond to slash command: {why}");
+    }
+}
+
+struct Handler {
+    store: SharedLogStore,
+    settings: SharedSettingsStore,
+    now_playing: SharedNowPlaying,
+    presences: SharedPresences,
+    rate_limiter: RateLimiter,
+    tasks_started: AtomicBool,
+}
 
 #[async_trait]
 impl EventHandler for Handler {
     async fn presence_update(&self, _ctx: Context, presence: Presence) {
-        let changed = presence.status.name();
+        let changed = Status::from(presence.status);
+        let playing = board::playing_activity(presence.status, &presence.activities);
         let activities = presence.activities;
         if let Some(guild) = presence.guild_id {
             if let Ok(member) = guild.member(_ctx.clone(), presence.user.id).await {
@@ -33,24 +96,51 @@ impl EventHandler for Handler {
                     return;
                 }
 
+                self.now_playing
+                    .lock()
+                    .unwrap()
+                    .update(guild, presence.user.id, playing);
+
+                let current = PresenceState {
+                    status: changed,
+                    activities: activities
+                        .iter()
+                        .map(|a| aliases::canonical(&a.name))
+                        .collect(),
+                };
+                let previous = watch::swap_presence(
+                    &self.presences,
+                    &*self.store,
+                    guild,
+                    presence.user.id,
+                    current.clone(),
+                );
+
+                // Custom statuses are free text set by the user, not something they are doing
                 let mut activity_str: String = "".to_string();
                 for activity in activities {
-                    activity_str = activity.name;
+                    if activity.kind != ActivityType::Custom {
+                        activity_str = activity.name;
+                    }
                 }
 
                 let unix_time = SystemTime::now()
                     .duration_since(UNIX_EPOCH)
                     .unwrap()
                     .as_secs() as i64;
-                new_log(NewLog {
-                    user_id: presence.user.id.into(),
-                    status: changed.to_string(),
-                    activity: activity_str,
-                    unix_time,
-                })
-                .unwrap_or_else(|err| {
-                    println!("Error while inserting into a database: {}", err);
-                });
+                self.store
+                    .insert(NewLog {
+                        user_id: presence.user.id.into(),
+                        status: changed,
+                        activity: activity_str,
+                        unix_time,
+                        guild_id: Some(guild.into()),
+                    })
+                    .unwrap_or_else(|err| {
+                        println!("Error while inserting into a database: {}", err);
+                    });
+
+                watch::notify(&_ctx, guild, presence.user.id, previous.as_ref(), &current).await;
             }
         }
     }
@@ -59,26 +149,149 @@ impl EventHandler for Handler {
         if let Interaction::Command(command) = interaction {
             let allowed_ids: Vec<u64> =
                 vec![976552221191835718, 363362909822124052, 467396986279034881];
-            if !allowed_ids.contains(&command.user.id.into()) {
-                return;
-            }
-
-            let content = match command.data.name.as_str() {
-                "check" => Some(commands::check::run(&command.data.options())),
-                "filter" => Some(commands::filter::run(&command.data.options())),
-                "whoplayed" => Some(commands::whoplayed::run(&command.data.options())),
-                "execute" => Some(commands::execute::run(&command.data.options())),
-                _ => Some("No command".to_string()),
+            let outcome = if !allowed_ids.contains(&command.user.id.into()) {
+                Outcome::Denied
+            } else {
+                match self.rate_limiter.check(command.user.id, &command.data.name) {
+                    Ok(()) => Outcome::Ok,
+                    Err(wait) => Outcome::RateLimited(wait),
+                }
             };
+            audit::record(&command, &outcome);
 
-            if let Some(content) = content {
-                let data = CreateInteractionResponseMessage::new()
-                    .content(content)
-                    .ephemeral(true);
-                let builder = CreateInteractionResponse::Message(data);
+            if let Outcome::Denied = outcome {
+                return;
+            }
+            if let Outcome::RateLimited(wait) = outcome {
+                let retry_at = unix_now() + wait.as_secs() as i64 + 1;
+                let data = text(format!(
+                    "Slow down! You can use this command again <t:{}:R>",
+                    retry_at
+                ));
+                let builder = CreateInteractionResponse::Message(data.ephemeral(true));
                 if let Err(why) = command.create_response(&ctx.http, builder).await {
                     println!("Cannot respond to slash command: {why}");
                 }
+                return;
+            }
+
+            if command.data.name == "export" {
+                let job = commands::export::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    command.user.id,
+                    self.store.clone(),
+                    self.settings.clone(),
+                );
+                return run_deferred(&ctx, &command, job).await;
+            }
+            if command.data.name == "admin" {
+                let job = commands::admin::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    self.settings.clone(),
+                );
+                return run_deferred(&ctx, &command, job).await;
+            }
+
+            let data = match command.data.name.as_str() {
+                "check" => {
+                    commands::check::run(&command.data.options(), command.guild_id, &*self.store)
+                }
+                "filter" => {
+                    commands::filter::run(&command.data.options(), command.guild_id, &*self.store)
+                }
+                "whoplayed" => commands::whoplayed::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    &*self.store,
+                ),
+                "execute" => text(commands::execute::run(&command.data.options())),
+                "together" => text(commands::together::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    &*self.store,
+                )),
+                "compare" => text(commands::compare::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    &*self.store,
+                )),
+                "heatmap" => commands::heatmap::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    command.user.id,
+                    &*self.store,
+                    &*self.settings,
+                ),
+                "timeline" => commands::timeline::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    command.user.id,
+                    &*self.store,
+                    &*self.settings,
+                ),
+                "settings" => text(commands::settings::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    command.user.id,
+                    command
+                        .member
+                        .as_ref()
+                        .and_then(|member| member.permissions),
+                    &*self.settings,
+                )),
+                "watch" => text(commands::watch::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    command.user.id,
+                    &*self.settings,
+                )),
+                "privacy" => text(commands::privacy::run(
+                    &command.data.options(),
+                    command.user.id,
+                    &*self.settings,
+                )),
+                "aliases" => text(commands::aliases::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    command.user.id,
+                    &*self.settings,
+                )),
+                "alerts" => text(commands::alerts::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    command.user.id,
+                    &*self.settings,
+                )),
+                "digest" => commands::digest::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    &*self.store,
+                    &*self.settings,
+                ),
+                "audit" => text(commands::audit::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    &*self.settings,
+                )),
+                "board" => text(commands::board::run(
+                    &command.data.options(),
+                    command.guild_id,
+                    &self.now_playing,
+                    &*self.settings,
+                )),
+                commands::show_activity::NAME => commands::show_activity::run(
+                    command.data.target(),
+                    command.guild_id,
+                    &*self.store,
+                ),
+                _ => text("No command".to_string()),
+            };
+
+            let builder = CreateInteractionResponse::Message(data.ephemeral(true));
+            if let Err(why) = command.create_response(&ctx.http, builder).await {
+                println!("Cannot respond to slash command: {why}");
             }
         }
     }
@@ -95,10 +308,34 @@ impl EventHandler for Handler {
                     commands::filter::register(),
                     commands::whoplayed::register(),
                     commands::execute::register(),
+                    commands::board::register(),
+                    commands::together::register(),
+                    commands::compare::register(),
+                    commands::heatmap::register(),
+                    commands::timeline::register(),
+                    commands::watch::register(),
+                    commands::privacy::register(),
+                    commands::alerts::register(),
+                    commands::aliases::register(),
+                    commands::digest::register(),
+                    commands::settings::register(),
+                    commands::show_activity::register(),
+                    commands::audit::register(),
+                    commands::export::register(),
+                    commands::admin::register(),
                 ],
             )
             .await;
         // _ = Command::create_global_command(&ctx.http, commands::check::register()).await;
+
+        // ready fires again after every reconnect, background tasks must only start once
+        if !self.tasks_started.swap(true, Ordering::SeqCst) {
+            uptime::spawn();
+            board::seed(&self.now_playing, &*self.store, &*self.settings);
+            board::spawn(ctx.clone(), self.now_playing.clone(), self.settings.clone());
+            alerts::spawn(ctx.clone(), self.store.clone());
+            scheduler::spawn(ctx.clone(), self.store.clone());
+        }
     }
 }
 
@@ -106,6 +343,14 @@ impl EventHandler for Handler {
 async fn main() {
     //assert!(false, "TODO: write tests for a Lexer");
     dotenv().ok();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+    if let Err(err) = migrate::setup() {
+        eprintln!("Cannot prepare the database: {}", err);
+        std::process::exit(1);
+    }
     // Login with a bot token from the environment
     let token = env::var("DISCORD_TOKEN").expect("Not found");
     println!("{}", token);
@@ -115,7 +360,14 @@ async fn main() {
 
     // Create a new instance of the Client, logging in as a bot.
     let mut client = Client::builder(&token, intents)
-        .event_handler(Handler)
+        .event_handler(Handler {
+            store: Arc::new(DatabaseStore),
+            settings: Arc::new(DatabaseStore),
+            now_playing: Default::default(),
+            presences: Default::default(),
+            rate_limiter: RateLimiter::from_env(),
+            tasks_started: AtomicBool::new(false),
+        })
         .await
         .expect("Err creating client");
 
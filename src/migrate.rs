@@ -0,0 +1,98 @@
+//! Schema migrations embedded from `migrations/<backend>/`, applied when the bot or a CLI
+//! command starts so a fresh deployment only needs `DATABASE_URL`. Both backends have the
+//! same migration versions, a schema change has to be written for each of them.
+
+use diesel::prelude::*;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+use crate::storage::{establish_connection, Backend, DbConnection, StorageError};
+
+#[cfg(not(feature = "postgres"))]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+#[cfg(feature = "postgres")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/postgres");
+
+/// Selects every column of every table known to `schema.rs`, so a database the code
+/// doesn't match is reported at startup rather than by the first query touching it.
+macro_rules! check_tables {
+    ($conn:expr, $($table:ident),* $(,)?) => {
+        $(
+            crate::schema::$table::table
+                .select(crate::schema::$table::all_columns)
+                .limit(0)
+                .execute($conn)
+                .map_err(|err| {
+                    StorageError::Migration(format!(
+                        "table {} doesn't match the schema: {}",
+                        stringify!($table),
+                        err
+                    ))
+                })?;
+        )*
+    };
+}
+
+/// Applies the pending migrations and checks that the schema matches the code.
+pub fn setup() -> Result<(), StorageError> {
+    let conn = &mut establish_connection()?;
+    for version in conn
+        .run_pending_migrations(MIGRATIONS)
+        .map_err(|err| StorageError::Migration(format!("cannot apply migrations: {}", err)))?
+    {
+        println!("Applied migration {}", version);
+    }
+    check_schema(conn)
+}
+
+pub fn check_schema(conn: &mut DbConnection) -> Result<(), StorageError> {
+    check_tables!(
+        conn,
+        activity_aliases,
+        activity_names,
+        alert_history,
+        alert_rules,
+        audit,
+        boards,
+        daily_activity,
+        daily_status,
+        digests,
+        guild_settings,
+        logs,
+        opt_outs,
+        rollup_state,
+        uptime,
+        user_settings,
+        watches,
+    );
+    Ok(())
+}
+
+/// Every embedded migration with whether it was applied.
+pub fn status() -> Result<Vec<(String, bool)>, StorageError> {
+    let conn = &mut establish_connection()?;
+    let applied = conn
+        .applied_migrations()
+        .map_err(|err| StorageError::Migration(err.to_string()))?;
+    let migrations = diesel::migration::MigrationSource::<Backend>::migrations(&MIGRATIONS)
+        .map_err(|err| StorageError::Migration(err.to_string()))?;
+    Ok(migrations
+        .iter()
+        .map(|migration| {
+            let name = migration.name();
+            (name.to_string(), applied.contains(&name.version()))
+        })
+        .collect())
+}
+
+/// Reverts the last `steps` applied migrations, returning their versions.
+pub fn rollback(steps: usize) -> Result<Vec<String>, StorageError> {
+    let conn = &mut establish_connection()?;
+    let mut reverted = Vec::new();
+    for _ in 0..steps {
+        let version = conn
+            .revert_last_migration(MIGRATIONS)
+            .map_err(|err| StorageError::Migration(format!("cannot revert migration: {}", err)))?;
+        reverted.push(version.to_string());
+    }
+    Ok(reverted)
+}
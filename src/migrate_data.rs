@@ -0,0 +1,240 @@
+//! Copies an existing SQLite database into the PostgreSQL database at `DATABASE_URL`,
+//! keeping every id so the references between tables stay valid. The daily rollups are
+//! rebuilt from the copied logs rather than copied.
+//!
+//! To try it against a local server:
+//!
+//! ```text
+//! createdb presence
+//! DATABASE_URL=postgres://localhost/presence cargo run --features postgres -- \
+//!     migrate-data --from bot.db
+//! ```
+
+use diesel::prelude::*;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+use crate::migrate;
+use crate::sessions::unix_now;
+use crate::storage::{
+    establish_connection, rollups, ActivityAlias, ActivityName, AlertHistory, AlertRule, Audit,
+    Board, Digest, Status, Uptime, Watch,
+};
+
+const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/sqlite");
+const BATCH_SIZE: i64 = 1000;
+/// Tables whose id comes from a sequence, which has to continue after the copied ids.
+const SERIAL_TABLES: &[&str] = &[
+    "activity_aliases",
+    "activity_names",
+    "alert_history",
+    "alert_rules",
+    "audit",
+    "logs",
+    "uptime",
+    "watches",
+];
+
+/// A log as stored, `Log` also carries the activity name from `activity_names`.
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::logs)]
+struct LogRow {
+    id: i32,
+    user_id: i64,
+    status: Status,
+    activity_id: i32,
+    unix_time: i64,
+    guild_id: Option<i64>,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::opt_outs)]
+struct OptOut {
+    user_id: i64,
+    opted_out_at: i64,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::guild_settings)]
+struct GuildSettings {
+    guild_id: i64,
+    timezone: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::user_settings)]
+struct UserSettings {
+    user_id: i64,
+    timezone: Option<String>,
+}
+
+/// Copies a table a page at a time, ordered by its primary key `$key`.
+macro_rules! copy_table {
+    ($source:expr, $target:expr, $copied:expr, $table:ident, $row:ty, $key:ident) => {{
+        use crate::schema::$table::dsl::*;
+        let mut count = 0;
+        let mut last = None;
+        loop {
+            let mut query = $table.into_boxed();
+            if let Some(last) = last {
+                query = query.filter($key.gt(last));
+            }
+            let rows: Vec<$row> = query
+                .order($key.asc())
+                .limit(BATCH_SIZE)
+                .select(<$row>::as_select())
+                .load($source)?;
+            diesel::insert_into($table).values(&rows).execute($target)?;
+            count += rows.len();
+            match rows.last() {
+                Some(row) if rows.len() as i64 == BATCH_SIZE => last = Some(row.$key),
+                _ => break,
+            }
+        }
+        $copied.push((stringify!($table), count));
+    }};
+}
+
+/// Copies every table of the SQLite database at `path`, which is brought up to date first,
+/// into the empty PostgreSQL database. Returns the number of rows copied per table.
+pub fn copy_from_sqlite(path: &str) -> Result<Vec<(&'static str, usize)>, String> {
+    let source = &mut SqliteConnection::establish(path)
+        .map_err(|err| format!("Cannot open {}: {}", path, err))?;
+    source
+        .run_pending_migrations(SQLITE_MIGRATIONS)
+        .map_err(|err| format!("Cannot update {}: {}", path, err))?;
+
+    migrate::setup().map_err(|err| err.to_string())?;
+    let target = &mut establish_connection().map_err(|err| err.to_string())?;
+    let existing: i64 = crate::schema::logs::table
+        .count()
+        .get_result(target)
+        .map_err(|err| err.to_string())?;
+    if existing > 0 {
+        return Err("The PostgreSQL database already has logs, refusing to copy".to_string());
+    }
+
+    target
+        .transaction(|target| {
+            let mut copied = Vec::new();
+            copy_table!(source, target, copied, activity_aliases, ActivityAlias, id);
+            copy_table!(source, target, copied, activity_names, ActivityName, id);
+            copy_table!(source, target, copied, logs, LogRow, id);
+            copy_table!(source, target, copied, boards, Board, guild_id);
+            copy_table!(source, target, copied, uptime, Uptime, id);
+            copy_table!(source, target, copied, watches, Watch, id);
+            copy_table!(source, target, copied, opt_outs, OptOut, user_id);
+            copy_table!(source, target, copied, alert_rules, AlertRule, id);
+            copy_table!(source, target, copied, alert_history, AlertHistory, id);
+            copy_table!(source, target, copied, digests, Digest, guild_id);
+            copy_table!(
+                source,
+                target,
+                copied,
+                guild_settings,
+                GuildSettings,
+                guild_id
+            );
+            copy_table!(source, target, copied, user_settings, UserSettings, user_id);
+            copy_table!(source, target, copied, audit, Audit, id);
+            let (_, rows) = rollups::rebuild(target, unix_now())?;
+            copied.push(("daily rollups", rows));
+
+            for table in SERIAL_TABLES {
+                diesel::sql_query(format!(
+                    "SELECT setval(pg_get_serial_sequence('{0}', 'id'), \
+                     COALESCE(MAX(id), 0) + 1, false) FROM {0}",
+                    table
+                ))
+                .execute(target)?;
+            }
+            Ok(copied)
+        })
+        .map_err(|err: diesel::result::Error| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::prelude::*;
+    use diesel::sql_types::BigInt;
+    use diesel_migrations::MigrationHarness;
+
+    use super::{copy_from_sqlite, SQLITE_MIGRATIONS};
+    use crate::storage::{establish_connection, DatabaseStore, LogQuery, LogStore, NewLog, Status};
+
+    #[derive(QueryableByName)]
+    struct Count {
+        #[diesel(sql_type = BigInt)]
+        count: i64,
+    }
+
+    fn count<C: Connection>(conn: &mut C, table: &str) -> i64
+    where
+        Count: QueryableByName<C::Backend>,
+        diesel::query_builder::SqlQuery: diesel::query_dsl::LoadQuery<'static, C, Count>,
+    {
+        diesel::sql_query(format!("SELECT COUNT(*) AS count FROM {}", table))
+            .get_result::<Count>(conn)
+            .unwrap()
+            .count
+    }
+
+    /// Copies a small SQLite database, with more logs than fit in one batch, into the
+    /// PostgreSQL database at `DATABASE_URL`, which has to be empty:
+    ///
+    /// ```text
+    /// DATABASE_URL=postgres://localhost/presence_test cargo test --features postgres -- --ignored
+    /// ```
+    #[test]
+    #[ignore]
+    fn copies_every_row() {
+        let path = std::env::temp_dir().join(format!("migrate-data-{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let source = &mut SqliteConnection::establish(path).unwrap();
+        source.run_pending_migrations(SQLITE_MIGRATIONS).unwrap();
+        for statement in [
+            "INSERT INTO activity_names (name, first_seen, last_seen, canonical) VALUES \
+             ('', 1760000000, 1760900000, ''), \
+             ('Dota 2', 1760000000, 1760900000, 'Dota 2')",
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 1500) \
+             INSERT INTO logs (status, activity_id, user_id, unix_time, guild_id) \
+             SELECT 1 + i % 4, 1 + i % 2, i % 7, 1760000000 + i * 600, 5 FROM n",
+            "INSERT INTO watches (guild_id, subscriber_id, target_id) VALUES (5, 1, 2)",
+            "INSERT INTO opt_outs (user_id, opted_out_at) VALUES (3, 1760000000)",
+            "INSERT INTO user_settings (user_id, timezone) VALUES (1, 'Europe/Kyiv')",
+        ] {
+            diesel::sql_query(statement).execute(source).unwrap();
+        }
+
+        let copied = copy_from_sqlite(path).unwrap();
+
+        let target = &mut establish_connection().unwrap();
+        for (table, rows) in &copied {
+            if *table == "daily rollups" {
+                assert!(*rows > 0);
+                continue;
+            }
+            assert_eq!(
+                *rows as i64,
+                count(source, table),
+                "copied rows of {}",
+                table
+            );
+            assert_eq!(*rows as i64, count(target, table), "rows of {}", table);
+        }
+        assert_eq!(count(target, "logs"), 1500);
+        std::fs::remove_file(path).unwrap();
+
+        // The ids continue after the copied ones
+        DatabaseStore
+            .insert(NewLog {
+                user_id: 1,
+                status: Status::Online,
+                activity: "CS2".to_string(),
+                unix_time: 1761000000,
+                guild_id: Some(5),
+            })
+            .unwrap();
+        let latest = DatabaseStore.first(LogQuery::new()).unwrap().unwrap();
+        assert_eq!((latest.id, latest.activity.as_str()), (1501, "CS2"));
+    }
+}
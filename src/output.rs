@@ -0,0 +1,245 @@
+use chrono::DateTime;
+use serenity::all::{CommandOptionType, ResolvedOption, ResolvedValue};
+use serenity::builder::{CreateAttachment, CreateCommandOption, CreateInteractionResponseMessage};
+
+use crate::storage::{Cursor, Log, Status};
+
+/// Longest reply sent as a message, Discord refuses messages over 2000 characters.
+pub const MAX_REPLY_LEN: usize = 1900;
+
+/// Replies longer than this are sent as a file.
+const MAX_INLINE_LEN: usize = MAX_REPLY_LEN;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Table,
+    Csv,
+    Json,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Text | Format::Table => "txt",
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+
+    /// Language of the code block the output is wrapped in when sent inline.
+    fn code_block(self) -> Option<&'static str> {
+        match self {
+            Format::Text => None,
+            Format::Table => Some(""),
+            Format::Csv => Some("csv"),
+            Format::Json => Some("json"),
+        }
+    }
+}
+
+/// The `format` option shared by every command that returns logs.
+pub fn format_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "format",
+        "How to show the results",
+    )
+    .add_string_choice("Text", "text")
+    .add_string_choice("Table", "table")
+    .add_string_choice("CSV", "csv")
+    .add_string_choice("JSON", "json")
+}
+
+pub fn parse_format(options: &[ResolvedOption]) -> Format {
+    for option in options {
+        if let ("format", ResolvedValue::String(value)) = (option.name, &option.value) {
+            return match *value {
+                "table" => Format::Table,
+                "csv" => Format::Csv,
+                "json" => Format::Json,
+                _ => Format::Text,
+            };
+        }
+    }
+    Format::Text
+}
+
+/// The `status` option of commands filtering logs by status.
+pub fn status_option() -> CreateCommandOption {
+    Status::CHOICES.iter().fold(
+        CreateCommandOption::new(CommandOptionType::String, "status", "Only this status"),
+        |option, status| option.add_string_choice(status.label(), status.as_str()),
+    )
+}
+
+pub fn parse_status(options: &[ResolvedOption]) -> Option<Status> {
+    options
+        .iter()
+        .find_map(|option| match (option.name, &option.value) {
+            ("status", ResolvedValue::String(value)) => value.parse().ok(),
+            _ => None,
+        })
+}
+
+/// The `cursor` option of paged commands, continuing where the previous page ended.
+pub fn cursor_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "cursor",
+        "Continue from the end of a previous page",
+    )
+}
+
+pub fn parse_cursor(options: &[ResolvedOption]) -> Result<Option<Cursor>, String> {
+    for option in options {
+        if let ("cursor", ResolvedValue::String(value)) = (option.name, &option.value) {
+            return value.parse().map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// The plain listing of `check` and `filter`. A full page ends with the cursor of the next one.
+pub fn log_lines(records: &[Log], limit: i64) -> String {
+    let mut res_string = String::new();
+    for record in records {
+        res_string = format!(
+            "{}\nStatus: {}    Activity: {}   Time: <t:{}:R>   ",
+            res_string, record.status, record.activity, record.unix_time
+        );
+    }
+    if let Some(last) = records.last().filter(|_| records.len() as i64 >= limit) {
+        res_string = format!("{}\nNext page: `{}`", res_string, Cursor::after(last));
+    }
+    res_string
+}
+
+fn utc(time: i64) -> String {
+    DateTime::from_timestamp(time, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// Quotes a CSV field when it contains a separator, a quote or a line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub const CSV_HEADER: &str = "id,guild_id,user_id,status,activity,unix_time,utc_time";
+
+pub fn csv_row(record: &Log) -> String {
+    format!(
+        "{},{},{},{},{},{},{}",
+        record.id,
+        record
+            .guild_id
+            .map(|guild| guild.to_string())
+            .unwrap_or_default(),
+        record.user_id,
+        record.status,
+        csv_field(&record.activity),
+        record.unix_time,
+        utc(record.unix_time)
+    )
+}
+
+/// Cuts `value` to `len` characters, ending it with `ellipsis` when something was cut.
+pub fn shorten(value: &str, len: usize, ellipsis: char) -> String {
+    if value.chars().count() <= len {
+        return value.to_string();
+    }
+    let mut res: String = value.chars().take(len - 1).collect();
+    res.push(ellipsis);
+    res
+}
+
+/// Lays `rows` out in columns separated by `|`, padding every cell to the widest one of
+/// its column. The columns listed in `right_aligned` are aligned to the right.
+pub fn columns<const N: usize>(rows: &[[String; N]], right_aligned: &[usize]) -> String {
+    let widths: Vec<usize> = (0..N)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .enumerate()
+                .map(
+                    |(column, (value, width))| match right_aligned.contains(&column) {
+                        true => format!("{:>width$}", value, width = width),
+                        false => format!("{:<width$}", value, width = width),
+                    },
+                )
+                .collect::<Vec<String>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn table(records: &[Log]) -> String {
+    let header = ["id", "user_id", "status", "activity", "time (UTC)"];
+    let mut rows: Vec<[String; 5]> = vec![header.map(String::from)];
+    for record in records {
+        rows.push([
+            record.id.to_string(),
+            record.user_id.to_string(),
+            record.status.to_string(),
+            record.activity.clone(),
+            utc(record.unix_time),
+        ]);
+    }
+    columns(&rows, &[])
+}
+
+/// Renders `records` in the requested format. `text` renders the command's usual reply,
+/// used for `Format::Text`. Outputs too long for a message are attached as a file.
+pub fn render_logs(
+    records: &[Log],
+    format: Format,
+    name: &str,
+    text: impl FnOnce(&[Log]) -> String,
+) -> CreateInteractionResponseMessage {
+    let message = CreateInteractionResponseMessage::new();
+    if records.is_empty() {
+        return message.content("Nothing was recorded in a database");
+    }
+    let output = match format {
+        Format::Text => text(records),
+        Format::Table => table(records),
+        Format::Csv => std::iter::once(CSV_HEADER.to_string())
+            .chain(records.iter().map(csv_row))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Format::Json => match serde_json::to_string_pretty(records) {
+            Ok(json) => json,
+            Err(err) => return message.content(err.to_string()),
+        },
+    };
+
+    let inline = match format.code_block() {
+        Some(language) => format!("```{}\n{}\n```", language, output),
+        None => output.clone(),
+    };
+    if inline.len() <= MAX_INLINE_LEN {
+        return message.content(inline);
+    }
+    message
+        .content(format!("{} results, see the attached file", records.len()))
+        .add_file(CreateAttachment::bytes(
+            output.into_bytes(),
+            format!("{}.{}", name, format.extension()),
+        ))
+}
@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serenity::all::UserId;
+
+/// `uses` invocations allowed per `period`.
+#[derive(Clone, Copy, Debug)]
+pub struct Limit {
+    pub uses: usize,
+    pub period: Duration,
+}
+
+/// Parses limits written as `uses/seconds`, e.g. `5/60`. `off` disables the limit.
+fn parse_limit(value: &str) -> Option<Option<Limit>> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("off") {
+        return Some(None);
+    }
+    let (uses, secs) = value.split_once('/')?;
+    let uses = uses.trim().parse::<usize>().ok()?;
+    let secs = secs.trim().parse::<u64>().ok()?;
+    if uses == 0 || secs == 0 {
+        return None;
+    }
+    Some(Some(Limit {
+        uses,
+        period: Duration::from_secs(secs),
+    }))
+}
+
+/// Per user and per command sliding window limits, configured in the environment:
+/// `RATE_LIMIT_DEFAULT` applies to every command, `RATE_LIMIT_<COMMAND>` overrides it for
+/// one command (e.g. `RATE_LIMIT_WHOPLAYED=2/60`) and `RATE_LIMIT_EXEMPT` lists user ids
+/// that are never limited.
+pub struct RateLimiter {
+    default: Option<Limit>,
+    commands: HashMap<String, Option<Limit>>,
+    exempt: HashSet<u64>,
+    uses: Mutex<HashMap<(UserId, String), VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn from_env() -> Self {
+        let mut default = Some(Limit {
+            uses: 5,
+            period: Duration::from_secs(60),
+        });
+        let mut commands = HashMap::new();
+        for (key, value) in env::vars() {
+            let Some(command) = key.strip_prefix("RATE_LIMIT_") else {
+                continue;
+            };
+            if command == "EXEMPT" {
+                continue;
+            }
+            let Some(limit) = parse_limit(&value) else {
+                println!("Ignoring {}={}, expected uses/seconds or off", key, value);
+                continue;
+            };
+            match command {
+                "DEFAULT" => default = limit,
+                _ => {
+                    commands.insert(command.to_string(), limit);
+                }
+            }
+        }
+        let exempt = env::var("RATE_LIMIT_EXEMPT")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|id| id.trim().parse::<u64>().ok())
+            .collect();
+        Self {
+            default,
+            commands,
+            exempt,
+            uses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limit(&self, command: &str) -> Option<Limit> {
+        let key = command.to_uppercase().replace([' ', '-'], "_");
+        self.commands.get(&key).copied().unwrap_or(self.default)
+    }
+
+    /// Records a use of `command` by `user`, or returns how long they have to wait when
+    /// they are over the limit.
+    pub fn check(&self, user: UserId, command: &str) -> Result<(), Duration> {
+        self.check_at(user, command, Instant::now())
+    }
+
+    fn check_at(&self, user: UserId, command: &str, now: Instant) -> Result<(), Duration> {
+        if self.exempt.contains(&user.get()) {
+            return Ok(());
+        }
+        let Some(limit) = self.limit(command) else {
+            return Ok(());
+        };
+
+        let mut uses = self.uses.lock().unwrap();
+        // Windows whose uses all expired are dropped, so users don't stay in memory forever
+        uses.retain(|(_, command), window| {
+            let period = self.limit(command).map(|limit| limit.period);
+            window
+                .back()
+                .zip(period)
+                .is_some_and(|(used, period)| now.duration_since(*used) < period)
+        });
+        let window = uses.entry((user, command.to_string())).or_default();
+        while window
+            .front()
+            .is_some_and(|used| now.duration_since(*used) >= limit.period)
+        {
+            window.pop_front();
+        }
+        if window.len() >= limit.uses {
+            let oldest = *window.front().unwrap();
+            return Err(limit.period - now.duration_since(oldest));
+        }
+        window.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use serenity::all::UserId;
+
+    use super::{Limit, RateLimiter};
+
+    #[test]
+    fn uses_expire_exactly_at_the_end_of_the_window() {
+        let limiter = RateLimiter {
+            default: Some(Limit {
+                uses: 2,
+                period: Duration::from_secs(60),
+            }),
+            commands: HashMap::new(),
+            exempt: HashSet::new(),
+            uses: Mutex::new(HashMap::new()),
+        };
+        let user = UserId::new(1);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        assert_eq!(limiter.check_at(user, "check", at(0)), Ok(()));
+        assert_eq!(limiter.check_at(user, "check", at(30)), Ok(()));
+        assert_eq!(
+            limiter.check_at(user, "check", at(59)),
+            Err(Duration::from_secs(1))
+        );
+        // Other commands and users have windows of their own
+        assert_eq!(limiter.check_at(user, "filter", at(59)), Ok(()));
+        assert_eq!(limiter.check_at(UserId::new(2), "check", at(59)), Ok(()));
+
+        assert_eq!(limiter.check_at(user, "check", at(60)), Ok(()));
+        assert_eq!(
+            limiter.check_at(user, "check", at(60)),
+            Err(Duration::from_secs(30))
+        );
+        assert_eq!(limiter.check_at(user, "check", at(90)), Ok(()));
+    }
+}
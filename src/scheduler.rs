@@ -0,0 +1,138 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::TimeZone;
+use chrono_tz::Tz;
+use cron::Schedule;
+use serenity::all::{ChannelId, Context, CreateMessage};
+
+use crate::digest;
+use crate::sessions::{parse_timezone, unix_now};
+use crate::storage::{
+    complete_rollups, get_digests, seal_rollups, set_digest_sent, LogStore, SharedLogStore,
+};
+
+const TICK_SECS: u64 = 60;
+
+/// Parses a standard 5 field cron expression: minute, hour, day of month, month and day
+/// of week. Days of week are best given by name, e.g. `0 18 * * Sun`.
+pub fn parse_schedule(expression: &str) -> Result<Schedule, String> {
+    if expression.split_whitespace().count() != 5 {
+        return Err(
+            "A schedule needs 5 fields: minute hour day-of-month month day-of-week".to_string(),
+        );
+    }
+    Schedule::from_str(&format!("0 {}", expression)).map_err(|err| err.to_string())
+}
+
+/// Unix time of the first run of `schedule` in `tz` strictly after `after`.
+pub fn next_run(schedule: &Schedule, tz: Tz, after: i64) -> Option<i64> {
+    let after = tz.timestamp_opt(after, 0).single()?;
+    schedule.after(&after).next().map(|next| next.timestamp())
+}
+
+/// Runs the scheduled jobs once a minute. A job whose run was missed while the bot was
+/// down runs once as soon as the bot is back.
+pub fn spawn(ctx: Context, store: SharedLogStore) {
+    // Building the rollups the first time takes a while, the jobs don't wait for it
+    tokio::task::spawn_blocking(|| match complete_rollups(unix_now()) {
+        Ok((logs, rows)) if logs > 0 => {
+            println!(
+                "Rolled up {} logs into {} rows of daily statistics",
+                logs, rows
+            )
+        }
+        Ok(_) => {}
+        Err(err) => println!("Error while building the daily rollups: {}", err),
+    });
+    tokio::spawn(async move {
+        #[cfg(not(feature = "postgres"))]
+        let mut backups = crate::backup::schedule().map(|schedule| (schedule, 0));
+        let mut interval = tokio::time::interval(Duration::from_secs(TICK_SECS));
+        loop {
+            interval.tick().await;
+            match tokio::task::spawn_blocking(|| seal_rollups(unix_now())).await {
+                Ok(Err(err)) => println!("Error while sealing the daily rollups: {}", err),
+                Err(err) => println!("Error while sealing the daily rollups: {}", err),
+                Ok(Ok(())) => {}
+            }
+            run_digests(&ctx, &*store).await;
+            #[cfg(not(feature = "postgres"))]
+            if let Some((schedule, last_attempt)) = &mut backups {
+                run_backup(schedule, last_attempt).await;
+            }
+        }
+    });
+}
+
+/// Snapshots the database when a run of `schedule` passed since the newest snapshot.
+/// `last_attempt` keeps a failing backup from being retried every tick.
+#[cfg(not(feature = "postgres"))]
+async fn run_backup(schedule: &Schedule, last_attempt: &mut i64) {
+    use crate::backup::{backup, backup_dir, describe, latest};
+    use crate::sessions::configured_timezone;
+
+    let dir = backup_dir();
+    let last = match latest(&dir) {
+        Ok(last) => last.unwrap_or(0).max(*last_attempt),
+        Err(err) => {
+            println!("Error while listing the backups: {}", err);
+            return;
+        }
+    };
+    let now = unix_now();
+    match next_run(schedule, configured_timezone(), last) {
+        Some(next) if next <= now => {}
+        _ => return,
+    }
+
+    *last_attempt = now;
+    match tokio::task::spawn_blocking(move || backup(&dir)).await {
+        Ok(Ok(path)) => println!("Backed up the database to {}", describe(&path)),
+        Ok(Err(err)) => println!("Error while backing up the database: {}", err),
+        Err(err) => println!("Error while backing up the database: {}", err),
+    }
+}
+
+async fn run_digests(ctx: &Context, store: &dyn LogStore) {
+    let digests = match get_digests() {
+        Ok(digests) => digests,
+        Err(err) => {
+            println!("Error while loading digests: {}", err);
+            return;
+        }
+    };
+
+    let now = unix_now();
+    for config in digests {
+        let (Ok(schedule), Ok(tz)) = (
+            parse_schedule(&config.schedule),
+            parse_timezone(&config.timezone),
+        ) else {
+            println!("Invalid digest schedule in guild {}", config.guild_id);
+            continue;
+        };
+        match next_run(&schedule, tz, config.last_sent) {
+            Some(next) if next <= now => {}
+            _ => continue,
+        }
+
+        let embed = match digest::build(store, config.guild_id, tz, now) {
+            Ok(embed) => embed,
+            Err(err) => {
+                println!("Error while building the digest: {}", err);
+                continue;
+            }
+        };
+        if let Err(err) = ChannelId::new(config.channel_id as u64)
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await
+        {
+            println!("Cannot post the digest: {}", err);
+            continue;
+        }
+        if let Err(err) = set_digest_sent(config.guild_id, now) {
+            println!("Error while saving the digest time: {}", err);
+        }
+    }
+}
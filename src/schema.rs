@@ -1,11 +1,183 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    activity_aliases (id) {
+        id -> Integer,
+        pattern -> Text,
+        is_regex -> Bool,
+        canonical -> Text,
+        creator_id -> BigInt,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    activity_names (id) {
+        id -> Integer,
+        name -> Text,
+        first_seen -> BigInt,
+        last_seen -> BigInt,
+        canonical -> Text,
+    }
+}
+
+diesel::table! {
+    alert_history (id) {
+        id -> Integer,
+        rule_id -> Integer,
+        guild_id -> BigInt,
+        user_id -> BigInt,
+        streak_start -> BigInt,
+        fired_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    alert_rules (id) {
+        id -> Integer,
+        guild_id -> BigInt,
+        creator_id -> BigInt,
+        target_id -> Nullable<BigInt>,
+        activity -> Nullable<Text>,
+        status -> Nullable<Text>,
+        min_secs -> BigInt,
+        channel_id -> Nullable<BigInt>,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    audit (id) {
+        id -> Integer,
+        guild_id -> Nullable<BigInt>,
+        invoker_id -> BigInt,
+        command -> Text,
+        options -> Text,
+        target_id -> Nullable<BigInt>,
+        unix_time -> BigInt,
+        outcome -> Text,
+    }
+}
+
+diesel::table! {
+    boards (guild_id) {
+        guild_id -> BigInt,
+        channel_id -> BigInt,
+        message_id -> Nullable<BigInt>,
+        include_activities -> Text,
+        exclude_activities -> Text,
+    }
+}
+
+diesel::table! {
+    daily_activity (day, guild_id, user_id, activity_id) {
+        day -> BigInt,
+        guild_id -> BigInt,
+        user_id -> BigInt,
+        activity_id -> Integer,
+        secs -> BigInt,
+    }
+}
+
+diesel::table! {
+    daily_status (day, guild_id, user_id, status) {
+        day -> BigInt,
+        guild_id -> BigInt,
+        user_id -> BigInt,
+        status -> SmallInt,
+        secs -> BigInt,
+    }
+}
+
+diesel::table! {
+    digests (guild_id) {
+        guild_id -> BigInt,
+        channel_id -> BigInt,
+        schedule -> Text,
+        timezone -> Text,
+        last_sent -> BigInt,
+    }
+}
+
+diesel::table! {
+    guild_settings (guild_id) {
+        guild_id -> BigInt,
+        timezone -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     logs (id) {
         id -> Integer,
-        status -> Text,
-        activity -> Text,
+        status -> SmallInt,
+        activity_id -> Integer,
         user_id -> BigInt,
         unix_time -> BigInt,
+        guild_id -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    opt_outs (user_id) {
+        user_id -> BigInt,
+        opted_out_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    rollup_state (id) {
+        id -> Integer,
+        sealed_until -> BigInt,
+        rebuilding -> Bool,
+    }
+}
+
+diesel::table! {
+    uptime (id) {
+        id -> Integer,
+        started_at -> BigInt,
+        last_seen -> BigInt,
     }
 }
+
+diesel::table! {
+    user_settings (user_id) {
+        user_id -> BigInt,
+        timezone -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    watches (id) {
+        id -> Integer,
+        guild_id -> BigInt,
+        subscriber_id -> BigInt,
+        target_id -> BigInt,
+        activity -> Nullable<Text>,
+        status -> Nullable<SmallInt>,
+        channel_id -> Nullable<BigInt>,
+        last_notified -> Nullable<BigInt>,
+    }
+}
+
+diesel::joinable!(daily_activity -> activity_names (activity_id));
+diesel::joinable!(logs -> activity_names (activity_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    activity_aliases,
+    activity_names,
+    alert_history,
+    alert_rules,
+    audit,
+    boards,
+    daily_activity,
+    daily_status,
+    digests,
+    guild_settings,
+    logs,
+    opt_outs,
+    rollup_state,
+    uptime,
+    user_settings,
+    watches,
+);
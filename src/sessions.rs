@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Timelike};
+use chrono_tz::Tz;
+
+use crate::storage::{
+    day_start, Log, LogQuery, LogStore, SettingsStore, Status, StorageError, Totals, DAY_SECS,
+};
+
+/// A stretch of time during which a user kept the same status and activity.
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub user_id: i64,
+    pub status: Status,
+    pub activity: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Session {
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+/// Loads the sessions of the logs matching `query` clipped to `[from, to)`, leaving out
+/// the time during which the bot was offline. Logs are only written when a presence
+/// changes, so every user's state at `from` is their latest log before it, however old.
+/// The time range and order of `query` are set here. Sessions last until the next log
+/// matching `query`, so it should only filter users and guilds.
+pub fn load_sessions(
+    store: &dyn LogStore,
+    from: i64,
+    to: i64,
+    query: LogQuery,
+) -> Result<Vec<Session>, StorageError> {
+    let mut records = store.latest_before(&query, from)?;
+    records.extend(store.query(&query.since(from).until(to).oldest_first())?);
+    let gaps = store.offline_gaps(from, to)?;
+    Ok(remove_gaps(build_sessions(&records, from, to), &gaps))
+}
+
+/// Time spent online per user, status and activity over `[from, to)` by the users of `guild`,
+/// or only by `users`. Whole UTC days are read from the daily rollups, only the partial days
+/// at either end and the days not rolled up yet are worked out from the logs.
+pub fn load_totals(
+    store: &dyn LogStore,
+    from: i64,
+    to: i64,
+    guild: Option<i64>,
+    users: Option<&[i64]>,
+) -> Result<Totals, StorageError> {
+    let mut query = LogQuery::new().guild(guild);
+    if let Some(users) = users {
+        query = query.users(users);
+    }
+
+    let mut totals = Totals::default();
+    let mut from_logs = vec![(from, to)];
+    if let Some(sealed) = store.rolled_up_until()? {
+        let (first_day, last_day) = (day_start(from + DAY_SECS - 1), day_start(to.min(sealed)));
+        if first_day < last_day {
+            totals = store.daily_totals(guild, users, first_day, last_day)?;
+            from_logs = vec![(from, first_day), (last_day, to)];
+        }
+    }
+    for (start, end) in from_logs {
+        if start >= end {
+            continue;
+        }
+        for session in load_sessions(store, start, end, query.clone())? {
+            totals.add(
+                session.user_id,
+                session.status,
+                &session.activity,
+                session.duration(),
+            );
+        }
+    }
+    Ok(totals)
+}
+
+/// Turns records ordered by time into sessions, each record lasting until the next
+/// record of the same user or until `to`.
+pub fn build_sessions(records: &[Log], from: i64, to: i64) -> Vec<Session> {
+    let mut per_user: HashMap<i64, Vec<&Log>> = HashMap::new();
+    for record in records {
+        per_user.entry(record.user_id).or_default().push(record);
+    }
+
+    let mut sessions = Vec::new();
+    for (user, records) in per_user {
+        for (index, record) in records.iter().enumerate() {
+            let end = records
+                .get(index + 1)
+                .map(|next| next.unix_time)
+                .unwrap_or(to);
+            let start = record.unix_time.max(from);
+            let end = end.min(to);
+            if start >= end {
+                continue;
+            }
+            sessions.push(Session {
+                user_id: user,
+                status: record.status,
+                activity: record.activity.clone(),
+                start,
+                end,
+            });
+        }
+    }
+    sessions.sort_by_key(|session| (session.user_id, session.start));
+    sessions
+}
+
+/// Cuts the sorted `gaps` out of every session, splitting sessions that span a gap.
+pub fn remove_gaps(sessions: Vec<Session>, gaps: &[(i64, i64)]) -> Vec<Session> {
+    if gaps.is_empty() {
+        return sessions;
+    }
+    let mut result = Vec::with_capacity(sessions.len());
+    for mut session in sessions {
+        for (gap_start, gap_end) in gaps {
+            if *gap_end <= session.start || *gap_start >= session.end {
+                continue;
+            }
+            if *gap_start > session.start {
+                result.push(Session {
+                    end: *gap_start,
+                    ..session.clone()
+                });
+            }
+            session.start = *gap_end;
+            if session.start >= session.end {
+                break;
+            }
+        }
+        if session.start < session.end {
+            result.push(session);
+        }
+    }
+    result
+}
+
+/// Merges back-to-back sessions of the same activity, so a status change in the middle
+/// of a game doesn't split it. Offline sessions and sessions without an activity are dropped.
+pub fn activity_sessions(sessions: &[Session]) -> Vec<Session> {
+    let mut merged: Vec<Session> = Vec::new();
+    for session in sessions {
+        if session.activity.is_empty() || session.status.is_offline() {
+            continue;
+        }
+        if let Some(last) = merged.last_mut() {
+            if last.user_id == session.user_id
+                && last.activity == session.activity
+                && last.end == session.start
+            {
+                last.end = session.end;
+                continue;
+            }
+        }
+        merged.push(session.clone());
+    }
+    merged
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+pub fn format_duration(secs: i64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, _) => format!("{}m", minutes),
+        _ => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// Returns the merged `(start, end)` intervals during which `user` wasn't offline.
+pub fn online_intervals(sessions: &[Session], user: i64) -> Vec<(i64, i64)> {
+    let mut intervals: Vec<(i64, i64)> = Vec::new();
+    for session in sessions {
+        if session.user_id != user || session.status.is_offline() {
+            continue;
+        }
+        match intervals.last_mut() {
+            Some(last) if last.1 >= session.start => last.1 = last.1.max(session.end),
+            _ => intervals.push((session.start, session.end)),
+        }
+    }
+    intervals
+}
+
+/// Total length of the time covered by both sorted interval lists.
+pub fn overlap_secs(a: &[(i64, i64)], b: &[(i64, i64)]) -> i64 {
+    let (mut i, mut j, mut total) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        let start = a[i].0.max(b[j].0);
+        let end = a[i].1.min(b[j].1);
+        if start < end {
+            total += end - start;
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    total
+}
+
+/// Default time zone for guilds without one, read from `TIMEZONE`.
+pub fn configured_timezone() -> Tz {
+    env::var("TIMEZONE")
+        .ok()
+        .and_then(|name| name.parse::<Tz>().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown time zone {}, use names like Europe/Kyiv", name))
+}
+
+/// Time zone used to bucket statistics into days and hours: the one chosen by the user,
+/// else the one of the guild, else the configured default.
+pub fn timezone_for(
+    settings: &dyn SettingsStore,
+    guild_id: Option<i64>,
+    user_id: Option<i64>,
+) -> Tz {
+    let user_timezone =
+        user_id.and_then(|user_id| settings.get_user_timezone(user_id).ok().flatten());
+    let guild_timezone =
+        || guild_id.and_then(|guild_id| settings.get_guild_timezone(guild_id).ok().flatten());
+    user_timezone
+        .or_else(guild_timezone)
+        .and_then(|name| parse_timezone(&name).ok())
+        .unwrap_or_else(configured_timezone)
+}
+
+/// Unix time of the local midnights starting and ending `day`.
+pub fn day_bounds(day: NaiveDate, tz: Tz) -> Option<(i64, i64)> {
+    let start = tz
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0)?)
+        .earliest()?;
+    let end = tz
+        .from_local_datetime(&day.succ_opt()?.and_hms_opt(0, 0, 0)?)
+        .earliest()?;
+    Some((start.timestamp(), end.timestamp()))
+}
+
+/// Splits `[start, end)` at every local hour boundary of `tz` and calls `f` with the local
+/// start time and length of each piece.
+pub fn split_by_hour(start: i64, end: i64, tz: Tz, mut f: impl FnMut(DateTime<Tz>, i64)) {
+    let mut time = start;
+    while time < end {
+        let Some(local) = tz.timestamp_opt(time, 0).single() else {
+            return;
+        };
+        let into_hour = (local.minute() * 60 + local.second()) as i64;
+        let next = (time - into_hour + 3600).min(end);
+        f(local, next - time);
+        time = next;
+    }
+}
+
+/// Parses durations such as `90m`, `4h` or `1h30m`, returning seconds.
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let mut total = 0;
+    let mut number = String::new();
+    for c in value.trim().to_lowercase().chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'd' | 'h' | 'm' | 's' if !number.is_empty() => {
+                let unit = match c {
+                    'd' => 24 * 60 * 60,
+                    'h' => 60 * 60,
+                    'm' => 60,
+                    _ => 1,
+                };
+                let secs = number.parse::<i64>().ok()?.checked_mul(unit)?;
+                total = secs.checked_add(total)?;
+                number.clear();
+            }
+            ' ' => {}
+            _ => return None,
+        }
+    }
+    if !number.is_empty() || total == 0 {
+        return None;
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use chrono_tz::Tz;
+
+    use super::{remove_gaps, split_by_hour, Session};
+    use crate::storage::Status::Online;
+
+    const HOUR: i64 = 60 * 60;
+    /// 2025-10-09 00:00 UTC.
+    const DAY: i64 = 1_759_968_000;
+
+    fn pieces(start: i64, end: i64, tz: Tz) -> Vec<(String, i64)> {
+        let mut pieces = Vec::new();
+        split_by_hour(start, end, tz, |local: DateTime<Tz>, secs| {
+            pieces.push((local.format("%H:%M").to_string(), secs))
+        });
+        pieces
+    }
+
+    #[test]
+    fn splits_sessions_crossing_an_hour() {
+        assert_eq!(
+            pieces(
+                DAY + 10 * HOUR + HOUR / 2,
+                DAY + 12 * HOUR + HOUR / 4,
+                Tz::UTC
+            ),
+            vec![
+                ("10:30".to_string(), HOUR / 2),
+                ("11:00".to_string(), HOUR),
+                ("12:00".to_string(), HOUR / 4),
+            ]
+        );
+        // The hours of a half hour offset start at half past in UTC
+        assert_eq!(
+            pieces(DAY + 10 * HOUR, DAY + 11 * HOUR, Tz::Asia__Kolkata),
+            vec![
+                ("15:30".to_string(), HOUR / 2),
+                ("16:00".to_string(), HOUR / 2)
+            ]
+        );
+        assert_eq!(
+            pieces(DAY + 10 * HOUR, DAY + 11 * HOUR, Tz::UTC),
+            vec![("10:00".to_string(), HOUR)]
+        );
+        assert!(pieces(DAY, DAY, Tz::UTC).is_empty());
+    }
+
+    #[test]
+    fn gaps_split_sessions() {
+        let session = |start: i64, end: i64| Session {
+            user_id: 1,
+            status: Online,
+            activity: String::new(),
+            start,
+            end,
+        };
+        let bounds = |sessions: Vec<Session>| {
+            sessions
+                .iter()
+                .map(|session| (session.start, session.end))
+                .collect::<Vec<(i64, i64)>>()
+        };
+
+        assert_eq!(
+            bounds(remove_gaps(vec![session(0, 100)], &[(20, 30), (50, 60)])),
+            vec![(0, 20), (30, 50), (60, 100)]
+        );
+        // Gaps touching a session leave it whole, gaps covering it remove it
+        assert_eq!(
+            bounds(remove_gaps(
+                vec![session(0, 100), session(100, 200), session(300, 400)],
+                &[(-10, 0), (200, 300), (400, 410)]
+            )),
+            vec![(0, 100), (100, 200), (300, 400)]
+        );
+        assert_eq!(
+            bounds(remove_gaps(
+                vec![session(0, 100), session(100, 200)],
+                &[(-10, 10), (90, 200)]
+            )),
+            vec![(10, 90)]
+        );
+    }
+}
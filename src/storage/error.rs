@@ -0,0 +1,93 @@
+use std::fmt::{self, Display};
+
+use diesel::result::{DatabaseErrorKind, Error};
+use diesel::ConnectionError;
+
+/// Why a storage operation failed. `Display` gives the full details for the logs, commands
+/// reply with `user_message` instead so database internals never reach Discord.
+#[derive(Debug)]
+pub enum StorageError {
+    /// `DATABASE_URL` is missing or the database cannot be opened.
+    Connection(String),
+    NotFound,
+    /// A unique, foreign key, not null or check constraint rejected a write.
+    Constraint(String),
+    /// The database stayed locked by another writer through every retry.
+    Busy(String),
+    /// Applying, reverting or checking migrations failed.
+    Migration(String),
+    /// Any other database error.
+    Query(String),
+}
+
+impl StorageError {
+    /// Locks are released once the other writer is done, so these are worth retrying.
+    pub fn is_busy(&self) -> bool {
+        matches!(self, StorageError::Busy(_))
+    }
+
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            StorageError::Connection(_) | StorageError::Migration(_) => {
+                "The database is unavailable right now, please try again later"
+            }
+            StorageError::NotFound => "Nothing was recorded in a database",
+            StorageError::Constraint(_) => "This conflicts with something already saved",
+            StorageError::Busy(_) => "The database is busy, please try again in a moment",
+            StorageError::Query(_) => "Something went wrong while reading the database",
+        }
+    }
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Connection(err) => write!(f, "cannot connect to the database: {}", err),
+            StorageError::NotFound => write!(f, "record not found"),
+            StorageError::Constraint(err) => write!(f, "constraint violated: {}", err),
+            StorageError::Busy(err) => write!(f, "database busy: {}", err),
+            StorageError::Migration(err) => write!(f, "migration failed: {}", err),
+            StorageError::Query(err) => write!(f, "query failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<Error> for StorageError {
+    fn from(err: Error) -> Self {
+        match &err {
+            Error::NotFound => StorageError::NotFound,
+            Error::DatabaseError(kind, info) => match kind {
+                DatabaseErrorKind::UniqueViolation
+                | DatabaseErrorKind::ForeignKeyViolation
+                | DatabaseErrorKind::NotNullViolation
+                | DatabaseErrorKind::CheckViolation => StorageError::Constraint(err.to_string()),
+                DatabaseErrorKind::SerializationFailure => StorageError::Busy(err.to_string()),
+                // SQLite reports SQLITE_BUSY and SQLITE_LOCKED without a kind of their own
+                _ if info.message().contains("database is locked")
+                    || info.message().contains("database table is locked") =>
+                {
+                    StorageError::Busy(err.to_string())
+                }
+                DatabaseErrorKind::ClosedConnection | DatabaseErrorKind::UnableToSendCommand => {
+                    StorageError::Connection(err.to_string())
+                }
+                _ => StorageError::Query(err.to_string()),
+            },
+            _ => StorageError::Query(err.to_string()),
+        }
+    }
+}
+
+impl From<ConnectionError> for StorageError {
+    fn from(err: ConnectionError) -> Self {
+        StorageError::Connection(err.to_string())
+    }
+}
+
+/// Logs the details of `err` and returns the message to show in Discord instead.
+pub fn user_error(err: StorageError) -> String {
+    println!("Storage error: {}", err);
+    err.user_message().to_string()
+}
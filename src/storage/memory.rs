@@ -0,0 +1,216 @@
+//! Logs kept in memory, for tests and for trying out commands without a database. The bot
+//! is never offline and nobody opts out.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::query::DistinctFilter;
+use super::{ActivityAlias, Log, LogQuery, LogStore, NewLog, Order, StorageError, Totals};
+use crate::aliases::AliasRules;
+
+pub struct MemoryStore {
+    logs: Mutex<Vec<Log>>,
+    aliases: AliasRules,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::with_aliases(&[])
+    }
+
+    /// A store naming activities after `aliases`, like the database does.
+    pub fn with_aliases(aliases: &[ActivityAlias]) -> Self {
+        Self {
+            logs: Mutex::new(Vec::new()),
+            aliases: AliasRules::new(aliases),
+        }
+    }
+}
+
+impl LogStore for MemoryStore {
+    fn insert(&self, log: NewLog) -> Result<(), StorageError> {
+        self.insert_many(&[log]).map(|_| ())
+    }
+
+    fn insert_many(&self, records: &[NewLog]) -> Result<usize, StorageError> {
+        let mut logs = self.logs.lock().unwrap();
+        for record in records {
+            let id = logs.last().map(|last| last.id).unwrap_or(0) + 1;
+            logs.push(Log {
+                id,
+                user_id: record.user_id,
+                status: record.status,
+                activity: self.aliases.canonical(&record.activity),
+                unix_time: record.unix_time,
+                guild_id: record.guild_id,
+            });
+        }
+        Ok(records.len())
+    }
+
+    fn get(&self, id: i32) -> Result<Log, StorageError> {
+        self.logs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|record| record.id == id)
+            .cloned()
+            .ok_or(StorageError::NotFound)
+    }
+
+    fn query(&self, query: &LogQuery) -> Result<Vec<Log>, StorageError> {
+        let mut records: Vec<Log> = self
+            .logs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| query.matches(record))
+            .cloned()
+            .collect();
+        records.sort_by_key(|record| (record.unix_time, record.id));
+        if query.order == Order::NewestFirst {
+            records.reverse();
+        }
+        let mut distinct = DistinctFilter::new(query.distinct);
+        records.retain(|record| distinct.keep(record));
+        if let Some(limit) = query.limit {
+            records.truncate(limit.max(0) as usize);
+        }
+        Ok(records)
+    }
+
+    fn latest_before(&self, query: &LogQuery, time: i64) -> Result<Vec<Log>, StorageError> {
+        let filter = LogQuery {
+            users: query.users.clone(),
+            excluded_users: query.excluded_users.clone(),
+            guild: query.guild,
+            ..LogQuery::new()
+        }
+        .until(time);
+        let mut latest: HashMap<i64, Log> = HashMap::new();
+        for record in self.logs.lock().unwrap().iter() {
+            if !filter.matches(record) {
+                continue;
+            }
+            match latest.get(&record.user_id) {
+                Some(known) if (known.unix_time, known.id) > (record.unix_time, record.id) => {}
+                _ => {
+                    latest.insert(record.user_id, record.clone());
+                }
+            }
+        }
+        Ok(latest.into_values().collect())
+    }
+
+    fn new_activities(&self, guild: i64, since: i64) -> Result<Vec<(String, i64)>, StorageError> {
+        let in_guild = LogQuery::new().guild(Some(guild));
+        let mut first_seen: HashMap<String, i64> = HashMap::new();
+        for record in self.logs.lock().unwrap().iter() {
+            if record.activity.is_empty() || !in_guild.matches(record) {
+                continue;
+            }
+            let time = first_seen
+                .entry(record.activity.clone())
+                .or_insert(record.unix_time);
+            *time = (*time).min(record.unix_time);
+        }
+        let mut activities: Vec<(String, i64)> = first_seen
+            .into_iter()
+            .filter(|(_, time)| *time >= since)
+            .collect();
+        activities.sort_by_key(|(_, time)| *time);
+        Ok(activities)
+    }
+
+    fn offline_gaps(&self, _from: i64, _to: i64) -> Result<Vec<(i64, i64)>, StorageError> {
+        Ok(Vec::new())
+    }
+
+    fn opted_out(&self) -> Result<Vec<i64>, StorageError> {
+        Ok(Vec::new())
+    }
+
+    /// Nothing is rolled up in memory, statistics are always worked out from the logs.
+    fn rolled_up_until(&self) -> Result<Option<i64>, StorageError> {
+        Ok(None)
+    }
+
+    fn daily_totals(
+        &self,
+        _guild: Option<i64>,
+        _users: Option<&[i64]>,
+        _from: i64,
+        _to: i64,
+    ) -> Result<Totals, StorageError> {
+        Ok(Totals::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryStore;
+    use crate::storage::{Cursor, LogQuery, LogStore, NewLog, Status};
+
+    /// Logs of two users, with two logs at the same time to check ties are broken by id.
+    fn store() -> MemoryStore {
+        let store = MemoryStore::new();
+        for (user_id, unix_time) in [(1, 100), (2, 150), (1, 200), (1, 200), (2, 250), (1, 300)] {
+            store
+                .insert(NewLog {
+                    user_id,
+                    status: Status::Online,
+                    activity: String::new(),
+                    unix_time,
+                    guild_id: None,
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    /// The ids of every page of `query`, following the cursor of the last log of each one.
+    fn pages(store: &MemoryStore, query: LogQuery) -> Vec<Vec<i32>> {
+        let mut pages = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = store.query(&query.clone().after(cursor)).unwrap();
+            let Some(last) = page.last() else {
+                return pages;
+            };
+            cursor = Some(Cursor::after(last));
+            pages.push(page.iter().map(|record| record.id).collect());
+        }
+    }
+
+    #[test]
+    fn pages_newest_first() {
+        let pages = pages(&store(), LogQuery::new().limit(2));
+        assert_eq!(pages, vec![vec![6, 5], vec![4, 3], vec![2, 1]]);
+    }
+
+    #[test]
+    fn pages_oldest_first_with_filters() {
+        let pages = pages(&store(), LogQuery::new().user(1).oldest_first().limit(3));
+        assert_eq!(pages, vec![vec![1, 3, 4], vec![6]]);
+    }
+
+    #[test]
+    fn cursor_round_trips_through_text() {
+        let cursor: Cursor = " 200-4 ".parse().unwrap();
+        assert_eq!(
+            cursor,
+            Cursor {
+                unix_time: 200,
+                id: 4
+            }
+        );
+        assert_eq!(cursor.to_string(), "200-4");
+        assert!("200".parse::<Cursor>().is_err());
+    }
+}
@@ -0,0 +1,1037 @@
+use diesel::prelude::*;
+use dotenv::dotenv;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+pub mod error;
+pub mod memory;
+pub mod query;
+pub mod rollups;
+pub mod settings;
+pub mod status;
+
+pub use error::{user_error, StorageError};
+pub use rollups::{complete_rollups, day_start, rebuild_rollups, seal_rollups, Totals, DAY_SECS};
+pub use settings::{SettingsStore, SharedSettingsStore};
+pub use status::Status;
+
+use crate::aliases::AliasRules;
+use crate::uptime::gaps_between;
+
+use query::DistinctFilter;
+pub use query::{ActivityMatch, Cursor, Distinct, LogQuery, Order};
+
+/// The database everything is stored in, SQLite unless built with the `postgres` feature.
+#[cfg(not(feature = "postgres"))]
+pub type Backend = diesel::sqlite::Sqlite;
+#[cfg(not(feature = "postgres"))]
+pub type DbConnection = SqliteConnection;
+#[cfg(feature = "postgres")]
+pub type Backend = diesel::pg::Pg;
+#[cfg(feature = "postgres")]
+pub type DbConnection = PgConnection;
+
+pub fn establish_connection() -> Result<DbConnection, StorageError> {
+    dotenv().ok();
+
+    let database_url = env::var("DATABASE_URL")
+        .map_err(|_| StorageError::Connection("DATABASE_URL must be set".to_string()))?;
+    Ok(DbConnection::establish(&database_url)?)
+}
+
+/// Times an operation is retried while another writer holds the database lock, waiting
+/// twice as long before each attempt.
+const BUSY_RETRIES: u32 = 5;
+const BUSY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Runs `operation` on a new connection, retrying it while the database is busy. A retry
+/// repeats the whole operation, so writes made of several statements need a transaction.
+fn run<T>(
+    mut operation: impl FnMut(&mut DbConnection) -> QueryResult<T>,
+) -> Result<T, StorageError> {
+    let conn = &mut establish_connection()?;
+    let mut attempt = 0;
+    loop {
+        match operation(conn).map_err(StorageError::from) {
+            Err(err) if err.is_busy() && attempt < BUSY_RETRIES => {
+                thread::sleep(BUSY_BACKOFF * 2u32.pow(attempt));
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A log with the canonical name of its activity, loaded from `logs` joined with
+/// `activity_names`.
+#[derive(Queryable, Selectable, Serialize, Clone, Debug)]
+#[diesel(table_name = crate::schema::logs)]
+#[diesel(check_for_backend(Backend))]
+pub struct Log {
+    pub id: i32,
+    pub user_id: i64,
+    pub status: Status,
+    #[diesel(select_expression = crate::schema::activity_names::canonical)]
+    #[diesel(select_expression_type = crate::schema::activity_names::canonical)]
+    pub activity: String,
+    pub unix_time: i64,
+    pub guild_id: Option<i64>,
+}
+
+/// A log to record, its activity is added to `activity_names` when it isn't there yet.
+pub struct NewLog {
+    pub user_id: i64,
+    pub status: Status,
+    pub activity: String,
+    pub unix_time: i64,
+    pub guild_id: Option<i64>,
+}
+
+/// A row of `logs` as stored, referring to its activity by id.
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::logs)]
+#[diesel(check_for_backend(Backend))]
+struct LogRow {
+    user_id: i64,
+    status: Status,
+    activity_id: i32,
+    unix_time: i64,
+    guild_id: Option<i64>,
+}
+
+/// Every activity name ever recorded, with the first and last time it was seen and the
+/// canonical name its logs are grouped under.
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::activity_names)]
+#[diesel(check_for_backend(Backend))]
+pub struct ActivityName {
+    pub id: i32,
+    pub name: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub canonical: String,
+}
+
+fn load_alias_rules(conn: &mut DbConnection) -> QueryResult<AliasRules> {
+    Ok(AliasRules::new(&load_alias_rows(conn)?))
+}
+
+/// Returns the ids of the activities in `seen`, which maps names to the earliest and latest
+/// time they were seen at. Missing names are added with the canonical name given by the
+/// alias rules, the seen times of the others are widened.
+fn activity_ids(
+    conn: &mut DbConnection,
+    seen: &HashMap<&str, (i64, i64)>,
+) -> QueryResult<HashMap<String, i32>> {
+    use crate::schema::activity_names::dsl::*;
+    let mut rules = None;
+    let mut ids = HashMap::new();
+    for (activity, (first, last)) in seen {
+        let known = diesel::select(diesel::dsl::exists(
+            activity_names.filter(name.eq(activity)),
+        ))
+        .get_result::<bool>(conn)?;
+        if !known {
+            if rules.is_none() {
+                rules = Some(load_alias_rules(conn)?);
+            }
+            let canonical_name = rules.as_ref().unwrap().canonical(activity);
+            diesel::insert_into(activity_names)
+                .values((
+                    name.eq(activity),
+                    first_seen.eq(first),
+                    last_seen.eq(last),
+                    canonical.eq(canonical_name),
+                ))
+                .on_conflict(name)
+                .do_nothing()
+                .execute(conn)?;
+        }
+        diesel::update(
+            activity_names
+                .filter(name.eq(activity))
+                .filter(first_seen.gt(first)),
+        )
+        .set(first_seen.eq(first))
+        .execute(conn)?;
+        diesel::update(
+            activity_names
+                .filter(name.eq(activity))
+                .filter(last_seen.lt(last)),
+        )
+        .set(last_seen.eq(last))
+        .execute(conn)?;
+        let activity_id = activity_names
+            .filter(name.eq(activity))
+            .select(id)
+            .first(conn)?;
+        ids.insert(activity.to_string(), activity_id);
+    }
+    Ok(ids)
+}
+
+fn insert_logs(conn: &mut DbConnection, records: &[NewLog]) -> QueryResult<usize> {
+    let mut seen: HashMap<&str, (i64, i64)> = HashMap::new();
+    for record in records {
+        let (first, last) = seen
+            .entry(record.activity.as_str())
+            .or_insert((record.unix_time, record.unix_time));
+        *first = (*first).min(record.unix_time);
+        *last = (*last).max(record.unix_time);
+    }
+    let ids = activity_ids(conn, &seen)?;
+    let rows: Vec<LogRow> = records
+        .iter()
+        .map(|record| LogRow {
+            user_id: record.user_id,
+            status: record.status,
+            activity_id: ids[&record.activity],
+            unix_time: record.unix_time,
+            guild_id: record.guild_id,
+        })
+        .collect();
+    let Some(state) = rollups::state(conn)? else {
+        return diesel::insert_into(crate::schema::logs::table)
+            .values(&rows)
+            .execute(conn);
+    };
+    // Every log cuts short the session before it, so they are rolled up one at a time
+    for row in &rows {
+        rollups::add_log(conn, row, state)?;
+        diesel::insert_into(crate::schema::logs::table)
+            .values(row)
+            .execute(conn)?;
+    }
+    Ok(rows.len())
+}
+
+pub type SharedLogStore = Arc<dyn LogStore>;
+
+/// Everything the bot reads from and writes to the presence logs. Commands and tasks only
+/// go through this trait, so they work the same against any backend.
+pub trait LogStore: Send + Sync {
+    fn insert(&self, log: NewLog) -> Result<(), StorageError>;
+
+    /// Inserts all `records` at once, none of them when one fails.
+    fn insert_many(&self, records: &[NewLog]) -> Result<usize, StorageError>;
+
+    fn get(&self, id: i32) -> Result<Log, StorageError>;
+
+    fn query(&self, query: &LogQuery) -> Result<Vec<Log>, StorageError>;
+
+    /// The latest log before `time` of every user passing the user and guild filters of
+    /// `query`, which tells what each of them was doing at `time`.
+    fn latest_before(&self, query: &LogQuery, time: i64) -> Result<Vec<Log>, StorageError>;
+
+    /// Returns the activities first recorded in `guild` at or after `since`, with the time
+    /// they were first seen there.
+    fn new_activities(&self, guild: i64, since: i64) -> Result<Vec<(String, i64)>, StorageError>;
+
+    /// The sorted `(start, end)` stretches of `[from, to)` during which the bot was offline
+    /// and recorded nothing. Time before the first recorded run is never a gap.
+    fn offline_gaps(&self, from: i64, to: i64) -> Result<Vec<(i64, i64)>, StorageError>;
+
+    /// The users who opted out, whose logs are never exported.
+    fn opted_out(&self) -> Result<Vec<i64>, StorageError>;
+
+    /// The time up to which the daily rollups are complete, `None` when there are none.
+    fn rolled_up_until(&self) -> Result<Option<i64>, StorageError>;
+
+    /// Totals of the UTC days in `[from, to)` from the daily rollups, of the users of
+    /// `guild` or only of `users`.
+    fn daily_totals(
+        &self,
+        guild: Option<i64>,
+        users: Option<&[i64]>,
+        from: i64,
+        to: i64,
+    ) -> Result<Totals, StorageError>;
+
+    fn first(&self, query: LogQuery) -> Result<Option<Log>, StorageError> {
+        Ok(self.query(&query.limit(1))?.into_iter().next())
+    }
+}
+
+/// Logs stored in the database at `DATABASE_URL`.
+pub struct DatabaseStore;
+
+/// Rows loaded at a time while looking for distinct records.
+const DISTINCT_PAGE_SIZE: i64 = 1000;
+
+impl LogStore for DatabaseStore {
+    fn insert(&self, log: NewLog) -> Result<(), StorageError> {
+        self.insert_many(&[log]).map(|_| ())
+    }
+
+    fn insert_many(&self, records: &[NewLog]) -> Result<usize, StorageError> {
+        run(|conn| conn.transaction(|conn| insert_logs(conn, records)))
+    }
+
+    fn get(&self, _id: i32) -> Result<Log, StorageError> {
+        use crate::schema::{activity_names, logs};
+        run(|conn| {
+            logs::table
+                .inner_join(activity_names::table)
+                .filter(logs::id.eq(_id))
+                .select(Log::as_select())
+                .first(conn)
+        })
+    }
+
+    fn query(&self, query: &LogQuery) -> Result<Vec<Log>, StorageError> {
+        if query.distinct.is_none() {
+            return run(|conn| {
+                let mut statement = query.to_diesel();
+                if let Some(limit) = query.limit {
+                    statement = statement.limit(limit);
+                }
+                statement.select(Log::as_select()).load(conn)
+            });
+        }
+
+        // Distinct records are picked out page by page, so a small limit doesn't need
+        // every matching row to be loaded
+        let mut distinct = DistinctFilter::new(query.distinct);
+        let mut records = Vec::new();
+        let mut page_query = query.clone();
+        loop {
+            let page = run(|conn| {
+                page_query
+                    .to_diesel()
+                    .limit(DISTINCT_PAGE_SIZE)
+                    .select(Log::as_select())
+                    .load(conn)
+            })?;
+            let last_page = (page.len() as i64) < DISTINCT_PAGE_SIZE;
+            page_query.after = page.last().map(Cursor::after);
+            for record in page {
+                if distinct.keep(&record) {
+                    records.push(record);
+                    if query.limit == Some(records.len() as i64) {
+                        return Ok(records);
+                    }
+                }
+            }
+            if last_page {
+                return Ok(records);
+            }
+        }
+    }
+
+    fn latest_before(&self, query: &LogQuery, time: i64) -> Result<Vec<Log>, StorageError> {
+        use crate::schema::logs::dsl::*;
+        run(|conn| {
+            let users = match &query.users {
+                Some(users) => users.clone(),
+                None => {
+                    let mut users = logs.select(user_id).distinct().into_boxed();
+                    if let Some(guild) = query.guild {
+                        users = users.filter(guild_id.eq(guild).or(guild_id.is_null()));
+                    }
+                    users.load(conn)?
+                }
+            };
+            // One lookup per user walks the (user_id, unix_time) index back from `time`
+            let mut records = Vec::new();
+            for user in users {
+                if query.excluded_users.contains(&user) {
+                    continue;
+                }
+                let latest = LogQuery::new()
+                    .user(user)
+                    .guild(query.guild)
+                    .until(time)
+                    .to_diesel()
+                    .select(Log::as_select())
+                    .first(conn)
+                    .optional()?;
+                records.extend(latest);
+            }
+            Ok(records)
+        })
+    }
+
+    fn new_activities(&self, guild: i64, since: i64) -> Result<Vec<(String, i64)>, StorageError> {
+        use crate::schema::activity_names::canonical;
+        use crate::schema::logs::dsl::*;
+        use diesel::dsl::min;
+        run(|conn| {
+            logs.inner_join(crate::schema::activity_names::table)
+                .filter(canonical.ne(""))
+                .filter(guild_id.eq(guild).or(guild_id.is_null()))
+                .group_by(canonical)
+                .having(min(unix_time).ge(since))
+                .select((canonical, min(unix_time).assume_not_null()))
+                .order(min(unix_time).asc())
+                .load(conn)
+        })
+    }
+
+    fn offline_gaps(&self, from: i64, to: i64) -> Result<Vec<(i64, i64)>, StorageError> {
+        let (runs, first) = get_uptime_between(from, to)?;
+        Ok(gaps_between(&runs, first, from, to))
+    }
+
+    fn opted_out(&self) -> Result<Vec<i64>, StorageError> {
+        get_opted_out()
+    }
+
+    fn rolled_up_until(&self) -> Result<Option<i64>, StorageError> {
+        run(rollups::sealed_until)
+    }
+
+    fn daily_totals(
+        &self,
+        guild: Option<i64>,
+        users: Option<&[i64]>,
+        from: i64,
+        to: i64,
+    ) -> Result<Totals, StorageError> {
+        run(|conn| rollups::load_totals(conn, guild, users, from, to))
+    }
+}
+
+pub fn add_activity_alias(alias: NewActivityAlias) -> Result<(), StorageError> {
+    use crate::schema::activity_aliases::dsl::*;
+    run(|conn| {
+        diesel::insert_into(activity_aliases)
+            .values(&alias)
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn get_activity_aliases() -> Result<Vec<ActivityAlias>, StorageError> {
+    run(load_alias_rows)
+}
+
+fn load_alias_rows(conn: &mut DbConnection) -> QueryResult<Vec<ActivityAlias>> {
+    use crate::schema::activity_aliases::dsl::*;
+    activity_aliases
+        .order(id.asc())
+        .select(ActivityAlias::as_select())
+        .load(conn)
+}
+
+pub fn delete_activity_alias(_id: i32) -> Result<bool, StorageError> {
+    use crate::schema::activity_aliases::dsl::*;
+    run(|conn| {
+        diesel::delete(activity_aliases.filter(id.eq(_id)))
+            .execute(conn)
+            .map(|deleted| deleted > 0)
+    })
+}
+
+/// Recomputes the canonical name of every recorded activity from the current alias rules,
+/// returning how many of them changed.
+pub fn apply_activity_aliases() -> Result<usize, StorageError> {
+    use crate::schema::activity_names::dsl::*;
+    run(|conn| {
+        conn.transaction(|conn| {
+            let rules = load_alias_rules(conn)?;
+            let names: Vec<(i32, String, String)> =
+                activity_names.select((id, name, canonical)).load(conn)?;
+            let mut changed = 0;
+            for (activity_id, raw, current) in names {
+                let updated = rules.canonical(&raw);
+                if updated != current {
+                    diesel::update(activity_names.filter(id.eq(activity_id)))
+                        .set(canonical.eq(updated))
+                        .execute(conn)?;
+                    changed += 1;
+                }
+            }
+            Ok(changed)
+        })
+    })
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::activity_aliases)]
+#[diesel(check_for_backend(Backend))]
+pub struct ActivityAlias {
+    pub id: i32,
+    pub pattern: String,
+    pub is_regex: bool,
+    pub canonical: String,
+    pub creator_id: i64,
+    pub created_at: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::activity_aliases)]
+pub struct NewActivityAlias {
+    pub pattern: String,
+    pub is_regex: bool,
+    pub canonical: String,
+    pub creator_id: i64,
+    pub created_at: i64,
+}
+
+pub fn get_boards() -> Result<Vec<Board>, StorageError> {
+    use crate::schema::boards::dsl::*;
+    run(|conn| boards.select(Board::as_select()).load(conn))
+}
+
+pub fn get_board(_guild_id: i64) -> Result<Option<Board>, StorageError> {
+    use crate::schema::boards::dsl::*;
+    run(|conn| {
+        boards
+            .filter(guild_id.eq(_guild_id))
+            .select(Board::as_select())
+            .first(conn)
+            .optional()
+    })
+}
+
+/// Creates or reconfigures the board of a guild. The stored message is kept only when
+/// the board stays in the same channel, otherwise a new one gets posted.
+pub fn save_board(board: NewBoard) -> Result<(), StorageError> {
+    use crate::schema::boards::dsl::*;
+    let kept_message = get_board(board.guild_id)?
+        .filter(|prev| prev.channel_id == board.channel_id)
+        .and_then(|prev| prev.message_id);
+    run(|conn| {
+        diesel::insert_into(boards)
+            .values((&board, message_id.eq(kept_message)))
+            .on_conflict(guild_id)
+            .do_update()
+            .set((
+                channel_id.eq(board.channel_id),
+                message_id.eq(kept_message),
+                include_activities.eq(&board.include_activities),
+                exclude_activities.eq(&board.exclude_activities),
+            ))
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn set_board_message(_guild_id: i64, _message_id: Option<i64>) -> Result<(), StorageError> {
+    use crate::schema::boards::dsl::*;
+    run(|conn| {
+        diesel::update(boards.filter(guild_id.eq(_guild_id)))
+            .set(message_id.eq(_message_id))
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn delete_board(_guild_id: i64) -> Result<bool, StorageError> {
+    use crate::schema::boards::dsl::*;
+    run(|conn| {
+        diesel::delete(boards.filter(guild_id.eq(_guild_id)))
+            .execute(conn)
+            .map(|deleted| deleted > 0)
+    })
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::boards)]
+#[diesel(check_for_backend(Backend))]
+pub struct Board {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub message_id: Option<i64>,
+    pub include_activities: String,
+    pub exclude_activities: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::boards)]
+pub struct NewBoard {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub include_activities: String,
+    pub exclude_activities: String,
+}
+
+/// Records the start of a new bot run and returns its id.
+pub fn start_uptime(now: i64) -> Result<i32, StorageError> {
+    use crate::schema::uptime::dsl::*;
+    run(|conn| {
+        conn.transaction(|conn| {
+            diesel::insert_into(uptime)
+                .values((started_at.eq(now), last_seen.eq(now)))
+                .execute(conn)?;
+            uptime
+                .select(diesel::dsl::max(id))
+                .first::<Option<i32>>(conn)
+        })
+    })?
+    .ok_or(StorageError::NotFound)
+}
+
+pub fn touch_uptime(_id: i32, now: i64) -> Result<(), StorageError> {
+    use crate::schema::uptime::dsl::*;
+    run(|conn| {
+        diesel::update(uptime.filter(id.eq(_id)))
+            .set(last_seen.eq(now))
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+/// Returns the bot runs overlapping `[from, to)` ordered by start, together with the start
+/// of the very first recorded run, before which nothing is known about the bot's uptime.
+pub fn get_uptime_between(from: i64, to: i64) -> Result<(Vec<Uptime>, Option<i64>), StorageError> {
+    run(|conn| load_uptime(conn, from, to))
+}
+
+fn load_uptime(
+    conn: &mut DbConnection,
+    from: i64,
+    to: i64,
+) -> QueryResult<(Vec<Uptime>, Option<i64>)> {
+    use crate::schema::uptime::dsl::*;
+    let first = uptime
+        .select(diesel::dsl::min(started_at))
+        .first::<Option<i64>>(conn)?;
+    let runs = uptime
+        .filter(last_seen.ge(from))
+        .filter(started_at.lt(to))
+        .order(started_at.asc())
+        .select(Uptime::as_select())
+        .load(conn)?;
+    Ok((runs, first))
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::uptime)]
+#[diesel(check_for_backend(Backend))]
+pub struct Uptime {
+    pub id: i32,
+    pub started_at: i64,
+    pub last_seen: i64,
+}
+
+pub fn add_watch(watch: NewWatch) -> Result<(), StorageError> {
+    use crate::schema::watches::dsl::*;
+    run(|conn| {
+        diesel::insert_into(watches)
+            .values(&watch)
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn get_watches_by_subscriber(
+    _guild_id: i64,
+    _subscriber_id: i64,
+) -> Result<Vec<Watch>, StorageError> {
+    use crate::schema::watches::dsl::*;
+    run(|conn| {
+        watches
+            .filter(guild_id.eq(_guild_id))
+            .filter(subscriber_id.eq(_subscriber_id))
+            .order(id.asc())
+            .select(Watch::as_select())
+            .load(conn)
+    })
+}
+
+pub fn get_watches_by_target(_guild_id: i64, _target_id: i64) -> Result<Vec<Watch>, StorageError> {
+    use crate::schema::watches::dsl::*;
+    run(|conn| {
+        watches
+            .filter(guild_id.eq(_guild_id))
+            .filter(target_id.eq(_target_id))
+            .select(Watch::as_select())
+            .load(conn)
+    })
+}
+
+/// Deletes a watch, only when it belongs to `_subscriber_id`.
+pub fn delete_watch(_id: i32, _subscriber_id: i64) -> Result<bool, StorageError> {
+    use crate::schema::watches::dsl::*;
+    run(|conn| {
+        diesel::delete(
+            watches
+                .filter(id.eq(_id))
+                .filter(subscriber_id.eq(_subscriber_id)),
+        )
+        .execute(conn)
+        .map(|deleted| deleted > 0)
+    })
+}
+
+pub fn set_watch_notified(_id: i32, time: i64) -> Result<(), StorageError> {
+    use crate::schema::watches::dsl::*;
+    run(|conn| {
+        diesel::update(watches.filter(id.eq(_id)))
+            .set(last_notified.eq(time))
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn is_opted_out(_user_id: i64) -> Result<bool, StorageError> {
+    use crate::schema::opt_outs::dsl::*;
+    run(|conn| {
+        diesel::select(diesel::dsl::exists(opt_outs.filter(user_id.eq(_user_id)))).get_result(conn)
+    })
+}
+
+pub fn get_opted_out() -> Result<Vec<i64>, StorageError> {
+    use crate::schema::opt_outs::dsl::*;
+    run(|conn| opt_outs.select(user_id).load(conn))
+}
+
+/// Opts a user out of being watched, removing every existing watch on them.
+pub fn opt_out(_user_id: i64, time: i64) -> Result<(), StorageError> {
+    use crate::schema::opt_outs::dsl::*;
+    use crate::schema::watches;
+    run(|conn| {
+        conn.transaction(|conn| {
+            diesel::insert_into(opt_outs)
+                .values((user_id.eq(_user_id), opted_out_at.eq(time)))
+                .on_conflict(user_id)
+                .do_update()
+                .set(opted_out_at.eq(time))
+                .execute(conn)?;
+            diesel::delete(watches::table.filter(watches::target_id.eq(_user_id))).execute(conn)
+        })
+        .map(|_| ())
+    })
+}
+
+pub fn opt_in(_user_id: i64) -> Result<bool, StorageError> {
+    use crate::schema::opt_outs::dsl::*;
+    run(|conn| {
+        diesel::delete(opt_outs.filter(user_id.eq(_user_id)))
+            .execute(conn)
+            .map(|deleted| deleted > 0)
+    })
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::watches)]
+#[diesel(check_for_backend(Backend))]
+pub struct Watch {
+    pub id: i32,
+    pub guild_id: i64,
+    pub subscriber_id: i64,
+    pub target_id: i64,
+    pub activity: Option<String>,
+    pub status: Option<Status>,
+    pub channel_id: Option<i64>,
+    pub last_notified: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::watches)]
+pub struct NewWatch {
+    pub guild_id: i64,
+    pub subscriber_id: i64,
+    pub target_id: i64,
+    pub activity: Option<String>,
+    pub status: Option<Status>,
+    pub channel_id: Option<i64>,
+}
+
+pub fn add_alert_rule(rule: NewAlertRule) -> Result<(), StorageError> {
+    use crate::schema::alert_rules::dsl::*;
+    run(|conn| {
+        diesel::insert_into(alert_rules)
+            .values(&rule)
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+/// Returns the alert rules of a guild, or of every guild when `_guild_id` is `None`.
+pub fn get_alert_rules(_guild_id: Option<i64>) -> Result<Vec<AlertRule>, StorageError> {
+    use crate::schema::alert_rules::dsl::*;
+    run(|conn| {
+        let mut query = alert_rules.into_boxed();
+        if let Some(_guild_id) = _guild_id {
+            query = query.filter(guild_id.eq(_guild_id));
+        }
+        query
+            .order(id.asc())
+            .select(AlertRule::as_select())
+            .load(conn)
+    })
+}
+
+pub fn delete_alert_rule(_id: i32, _guild_id: i64) -> Result<bool, StorageError> {
+    use crate::schema::alert_rules::dsl::*;
+    run(|conn| {
+        diesel::delete(
+            alert_rules
+                .filter(id.eq(_id))
+                .filter(guild_id.eq(_guild_id)),
+        )
+        .execute(conn)
+        .map(|deleted| deleted > 0)
+    })
+}
+
+pub fn add_alert_history(entry: NewAlertHistory) -> Result<(), StorageError> {
+    use crate::schema::alert_history::dsl::*;
+    run(|conn| {
+        diesel::insert_into(alert_history)
+            .values(&entry)
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+/// Returns the alerts fired at or after `since`, newest first, optionally limited to a guild.
+pub fn get_alert_history(
+    _guild_id: Option<i64>,
+    since: i64,
+    limit: i64,
+) -> Result<Vec<AlertHistory>, StorageError> {
+    use crate::schema::alert_history::dsl::*;
+    run(|conn| {
+        let mut query = alert_history.filter(fired_at.ge(since)).into_boxed();
+        if let Some(_guild_id) = _guild_id {
+            query = query.filter(guild_id.eq(_guild_id));
+        }
+        query
+            .order(fired_at.desc())
+            .limit(limit)
+            .select(AlertHistory::as_select())
+            .load(conn)
+    })
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::alert_rules)]
+#[diesel(check_for_backend(Backend))]
+pub struct AlertRule {
+    pub id: i32,
+    pub guild_id: i64,
+    pub creator_id: i64,
+    pub target_id: Option<i64>,
+    pub activity: Option<String>,
+    pub status: Option<String>,
+    pub min_secs: i64,
+    pub channel_id: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::alert_rules)]
+pub struct NewAlertRule {
+    pub guild_id: i64,
+    pub creator_id: i64,
+    pub target_id: Option<i64>,
+    pub activity: Option<String>,
+    pub status: Option<String>,
+    pub min_secs: i64,
+    pub channel_id: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::alert_history)]
+#[diesel(check_for_backend(Backend))]
+pub struct AlertHistory {
+    pub id: i32,
+    pub rule_id: i32,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub streak_start: i64,
+    pub fired_at: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::alert_history)]
+pub struct NewAlertHistory {
+    pub rule_id: i32,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub streak_start: i64,
+    pub fired_at: i64,
+}
+
+pub fn get_last_alert_fired(_rule_id: i32, _user_id: i64) -> Result<Option<i64>, StorageError> {
+    use crate::schema::alert_history::dsl::*;
+    run(|conn| {
+        alert_history
+            .filter(rule_id.eq(_rule_id))
+            .filter(user_id.eq(_user_id))
+            .select(diesel::dsl::max(fired_at))
+            .first(conn)
+    })
+}
+
+pub fn save_digest(digest: Digest) -> Result<(), StorageError> {
+    use crate::schema::digests::dsl::*;
+    run(|conn| {
+        diesel::insert_into(digests)
+            .values(&digest)
+            .on_conflict(guild_id)
+            .do_update()
+            .set(&digest)
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn get_digests() -> Result<Vec<Digest>, StorageError> {
+    use crate::schema::digests::dsl::*;
+    run(|conn| digests.select(Digest::as_select()).load(conn))
+}
+
+pub fn get_digest(_guild_id: i64) -> Result<Option<Digest>, StorageError> {
+    use crate::schema::digests::dsl::*;
+    run(|conn| {
+        digests
+            .filter(guild_id.eq(_guild_id))
+            .select(Digest::as_select())
+            .first(conn)
+            .optional()
+    })
+}
+
+pub fn set_digest_sent(_guild_id: i64, time: i64) -> Result<(), StorageError> {
+    use crate::schema::digests::dsl::*;
+    run(|conn| {
+        diesel::update(digests.filter(guild_id.eq(_guild_id)))
+            .set(last_sent.eq(time))
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn delete_digest(_guild_id: i64) -> Result<bool, StorageError> {
+    use crate::schema::digests::dsl::*;
+    run(|conn| {
+        diesel::delete(digests.filter(guild_id.eq(_guild_id)))
+            .execute(conn)
+            .map(|deleted| deleted > 0)
+    })
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Debug)]
+#[diesel(table_name = crate::schema::digests)]
+#[diesel(primary_key(guild_id))]
+#[diesel(check_for_backend(Backend))]
+pub struct Digest {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub schedule: String,
+    pub timezone: String,
+    pub last_sent: i64,
+}
+
+pub fn get_guild_timezone(_guild_id: i64) -> Result<Option<String>, StorageError> {
+    use crate::schema::guild_settings::dsl::*;
+    run(|conn| {
+        guild_settings
+            .filter(guild_id.eq(_guild_id))
+            .select(timezone)
+            .first::<Option<String>>(conn)
+            .optional()
+            .map(Option::flatten)
+    })
+}
+
+/// Sets the time zone of a guild, `None` going back to the default one.
+pub fn set_guild_timezone(_guild_id: i64, _timezone: Option<String>) -> Result<(), StorageError> {
+    use crate::schema::guild_settings::dsl::*;
+    run(|conn| {
+        diesel::insert_into(guild_settings)
+            .values((guild_id.eq(_guild_id), timezone.eq(&_timezone)))
+            .on_conflict(guild_id)
+            .do_update()
+            .set(timezone.eq(&_timezone))
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn get_user_timezone(_user_id: i64) -> Result<Option<String>, StorageError> {
+    use crate::schema::user_settings::dsl::*;
+    run(|conn| {
+        user_settings
+            .filter(user_id.eq(_user_id))
+            .select(timezone)
+            .first::<Option<String>>(conn)
+            .optional()
+            .map(Option::flatten)
+    })
+}
+
+/// Sets the time zone of a user, `None` going back to the one of the guild.
+pub fn set_user_timezone(_user_id: i64, _timezone: Option<String>) -> Result<(), StorageError> {
+    use crate::schema::user_settings::dsl::*;
+    run(|conn| {
+        diesel::insert_into(user_settings)
+            .values((user_id.eq(_user_id), timezone.eq(&_timezone)))
+            .on_conflict(user_id)
+            .do_update()
+            .set(timezone.eq(&_timezone))
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn add_audit(entry: NewAudit) -> Result<(), StorageError> {
+    use crate::schema::audit::dsl::*;
+    run(|conn| {
+        diesel::insert_into(audit)
+            .values(&entry)
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+/// Returns the latest audit entries of a guild, newest first, optionally only the ones
+/// made by `invoker` or about `target`. Invocations in DMs have no guild and are never
+/// returned, they are only kept for whoever reads the database itself.
+pub fn get_audit(
+    _guild_id: i64,
+    invoker: Option<i64>,
+    target: Option<i64>,
+    limit: i64,
+) -> Result<Vec<Audit>, StorageError> {
+    use crate::schema::audit::dsl::*;
+    run(|conn| {
+        let mut query = audit.filter(guild_id.eq(_guild_id)).into_boxed();
+        if let Some(invoker) = invoker {
+            query = query.filter(invoker_id.eq(invoker));
+        }
+        if let Some(target) = target {
+            query = query.filter(target_id.eq(target));
+        }
+        query
+            .order(id.desc())
+            .limit(limit)
+            .select(Audit::as_select())
+            .load(conn)
+    })
+}
+
+#[derive(Queryable, Selectable, Insertable, Clone, Debug)]
+#[diesel(table_name = crate::schema::audit)]
+#[diesel(check_for_backend(Backend))]
+pub struct Audit {
+    pub id: i32,
+    pub guild_id: Option<i64>,
+    pub invoker_id: i64,
+    pub command: String,
+    pub options: String,
+    pub target_id: Option<i64>,
+    pub unix_time: i64,
+    pub outcome: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::audit)]
+pub struct NewAudit {
+    pub guild_id: Option<i64>,
+    pub invoker_id: i64,
+    pub command: String,
+    pub options: String,
+    pub target_id: Option<i64>,
+    pub unix_time: i64,
+    pub outcome: String,
+}
@@ -0,0 +1,312 @@
+//! A description of which logs to load, shared by every backend so commands build their
+//! lookups the same way no matter where the logs are stored.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use diesel::prelude::*;
+
+use super::{Backend, Log, Status};
+use crate::schema::{activity_names, logs};
+
+/// Logs joined with their activity names, as `LogQuery` filters them.
+pub(super) type LogSource = diesel::dsl::IntoBoxed<
+    'static,
+    diesel::dsl::InnerJoin<logs::table, activity_names::table>,
+    Backend,
+>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActivityMatch {
+    /// Records with exactly this activity.
+    Exact(String),
+    /// Records whose activity contains this text, ignoring case.
+    Fuzzy(String),
+    /// Records with any activity at all.
+    Any,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Order {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+}
+
+/// Keep only the first record, in the requested order, of every user or activity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distinct {
+    User,
+    Activity,
+}
+
+/// Position after the last record of a page, in the order the query sorts by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cursor {
+    pub unix_time: i64,
+    pub id: i32,
+}
+
+impl Cursor {
+    pub fn after(record: &Log) -> Self {
+        Self {
+            unix_time: record.unix_time,
+            id: record.id,
+        }
+    }
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.unix_time, self.id)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value
+            .trim()
+            .split_once('-')
+            .and_then(|(time, id)| {
+                Some(Self {
+                    unix_time: time.parse().ok()?,
+                    id: id.parse().ok()?,
+                })
+            })
+            .ok_or(format!("Invalid cursor {}", value))
+    }
+}
+
+/// Which logs to load and in what order, built by chaining filters:
+///
+/// ```ignore
+/// LogQuery::new().user(id).activity("Dota 2").since(from).limit(10)
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LogQuery {
+    pub users: Option<Vec<i64>>,
+    pub excluded_users: Vec<i64>,
+    pub activity: Option<ActivityMatch>,
+    pub status: Option<Status>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub guild: Option<i64>,
+    pub order: Order,
+    pub after: Option<Cursor>,
+    pub distinct: Option<Distinct>,
+    pub limit: Option<i64>,
+}
+
+impl LogQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn user(self, user: i64) -> Self {
+        self.users(&[user])
+    }
+
+    pub fn users(mut self, users: &[i64]) -> Self {
+        self.users = Some(users.to_vec());
+        self
+    }
+
+    pub fn exclude_users(mut self, users: &[i64]) -> Self {
+        self.excluded_users.extend_from_slice(users);
+        self
+    }
+
+    pub fn activity(mut self, name: &str) -> Self {
+        self.activity = Some(ActivityMatch::Exact(name.to_string()));
+        self
+    }
+
+    pub fn activity_like(mut self, text: &str) -> Self {
+        self.activity = Some(ActivityMatch::Fuzzy(text.to_string()));
+        self
+    }
+
+    pub fn any_activity(mut self) -> Self {
+        self.activity = Some(ActivityMatch::Any);
+        self
+    }
+
+    pub fn status(mut self, status: Status) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Records at or after `from`.
+    pub fn since(mut self, from: i64) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Records before `to`.
+    pub fn until(mut self, to: i64) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    /// Records in `[from, to)`, either end left open when `None`.
+    pub fn between(mut self, from: Option<i64>, to: Option<i64>) -> Self {
+        (self.from, self.to) = (from, to);
+        self
+    }
+
+    /// Records of `guild`, as well as the ones logged before guilds were recorded.
+    pub fn guild(mut self, guild: Option<i64>) -> Self {
+        self.guild = guild;
+        self
+    }
+
+    pub fn oldest_first(mut self) -> Self {
+        self.order = Order::OldestFirst;
+        self
+    }
+
+    pub fn after(mut self, cursor: Option<Cursor>) -> Self {
+        self.after = cursor;
+        self
+    }
+
+    pub fn distinct(mut self, distinct: Distinct) -> Self {
+        self.distinct = Some(distinct);
+        self
+    }
+
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Whether `record` passes every filter, the cursor included.
+    pub fn matches(&self, record: &Log) -> bool {
+        let activity = match &self.activity {
+            Some(ActivityMatch::Exact(name)) => record.activity == *name,
+            Some(ActivityMatch::Fuzzy(text)) => record
+                .activity
+                .to_lowercase()
+                .contains(&text.to_lowercase()),
+            Some(ActivityMatch::Any) => !record.activity.is_empty(),
+            None => true,
+        };
+        let after = match (self.after, self.order) {
+            (None, _) => true,
+            (Some(cursor), Order::NewestFirst) => {
+                (record.unix_time, record.id) < (cursor.unix_time, cursor.id)
+            }
+            (Some(cursor), Order::OldestFirst) => {
+                (record.unix_time, record.id) > (cursor.unix_time, cursor.id)
+            }
+        };
+        activity
+            && after
+            && self
+                .users
+                .as_ref()
+                .is_none_or(|users| users.contains(&record.user_id))
+            && !self.excluded_users.contains(&record.user_id)
+            && self
+                .status
+                .as_ref()
+                .is_none_or(|status| record.status == *status)
+            && self.from.is_none_or(|from| record.unix_time >= from)
+            && self.to.is_none_or(|to| record.unix_time < to)
+            && self
+                .guild
+                .is_none_or(|guild| record.guild_id.is_none_or(|id| id == guild))
+    }
+
+    /// The filters, cursor and order of this query as a diesel query. Limit and distinct
+    /// are left to the caller.
+    pub(super) fn to_diesel(&self) -> LogSource {
+        use crate::schema::activity_names::canonical as activity;
+        use crate::schema::logs::dsl::*;
+        let mut query = logs.inner_join(activity_names::table).into_boxed();
+        if let Some(users) = &self.users {
+            query = query.filter(user_id.eq_any(users.clone()));
+        }
+        if !self.excluded_users.is_empty() {
+            query = query.filter(user_id.ne_all(self.excluded_users.clone()));
+        }
+        match &self.activity {
+            Some(ActivityMatch::Exact(name)) => query = query.filter(activity.eq(name.clone())),
+            Some(ActivityMatch::Fuzzy(text)) => {
+                let pattern = format!(
+                    "%{}%",
+                    text.replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_")
+                );
+                // LIKE ignores case in SQLite, PostgreSQL needs ILIKE for that
+                #[cfg(not(feature = "postgres"))]
+                let condition = activity.like(pattern).escape('\\');
+                #[cfg(feature = "postgres")]
+                let condition = activity.ilike(pattern).escape('\\');
+                query = query.filter(condition);
+            }
+            Some(ActivityMatch::Any) => query = query.filter(activity.ne("")),
+            None => {}
+        }
+        if let Some(value) = self.status {
+            query = query.filter(status.eq(value));
+        }
+        if let Some(from) = self.from {
+            query = query.filter(unix_time.ge(from));
+        }
+        if let Some(to) = self.to {
+            query = query.filter(unix_time.lt(to));
+        }
+        if let Some(guild) = self.guild {
+            query = query.filter(guild_id.eq(guild).or(guild_id.is_null()));
+        }
+        match self.order {
+            Order::NewestFirst => {
+                if let Some(cursor) = self.after {
+                    query = query.filter(
+                        unix_time
+                            .lt(cursor.unix_time)
+                            .or(unix_time.eq(cursor.unix_time).and(id.lt(cursor.id))),
+                    );
+                }
+                query.order((unix_time.desc(), id.desc()))
+            }
+            Order::OldestFirst => {
+                if let Some(cursor) = self.after {
+                    query = query.filter(
+                        unix_time
+                            .gt(cursor.unix_time)
+                            .or(unix_time.eq(cursor.unix_time).and(id.gt(cursor.id))),
+                    );
+                }
+                query.order((unix_time.asc(), id.asc()))
+            }
+        }
+    }
+}
+
+/// Tells which records of a sorted list are the first of their user or activity.
+pub(super) struct DistinctFilter {
+    distinct: Option<Distinct>,
+    seen: std::collections::HashSet<String>,
+}
+
+impl DistinctFilter {
+    pub(super) fn new(distinct: Option<Distinct>) -> Self {
+        Self {
+            distinct,
+            seen: Default::default(),
+        }
+    }
+
+    pub(super) fn keep(&mut self, record: &Log) -> bool {
+        match self.distinct {
+            Some(Distinct::User) => self.seen.insert(record.user_id.to_string()),
+            Some(Distinct::Activity) => self.seen.insert(record.activity.clone()),
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,745 @@
+//! Seconds spent online per UTC day, per user and status and per user and activity, so
+//! statistics over weeks or months don't have to replay every log.
+//!
+//! A log lasts until the next log of the same user in the same guild, minus the time the
+//! bot was offline, like the sessions built from logs. Logs recorded before guilds were last
+//! until the next log of the user in any guild. The rollups hold every such session
+//! up to `sealed_until`, the last UTC midnight the scheduler reached: a session still open
+//! then is rolled up to it, and further once the next midnight is sealed. Inserting a log
+//! shortens the session before it, so the rollups stay exact even for backfilled logs.
+//!
+//! A rebuild rolls the logs up again one day at a time, each day in its own transaction so
+//! logs keep being recorded meanwhile. Until it is done `sealed_until` is the end of the
+//! days rebuilt so far and logs coming in only change the rollups before it.
+
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+
+use super::{load_uptime, run, Backend, DbConnection, LogRow, Status, StorageError};
+use crate::schema::{activity_names, daily_activity, daily_status, logs, rollup_state};
+use crate::uptime::gaps_between;
+
+pub const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Guild the logs recorded without one are rolled up under.
+const NO_GUILD: i64 = 0;
+
+/// Rows inserted at a time while rebuilding.
+const BATCH_SIZE: usize = 1000;
+
+/// How far the rollups go, stored in `rollup_state`.
+#[derive(Queryable, Selectable, Clone, Copy, Debug)]
+#[diesel(table_name = rollup_state)]
+#[diesel(check_for_backend(Backend))]
+pub(super) struct State {
+    sealed_until: i64,
+    rebuilding: bool,
+}
+
+impl State {
+    /// Nothing is rolled up past this time.
+    fn limit(&self) -> i64 {
+        match self.rebuilding {
+            true => self.sealed_until,
+            false => i64::MAX,
+        }
+    }
+}
+
+/// The UTC midnight starting the day of `time`.
+pub fn day_start(time: i64) -> i64 {
+    time.div_euclid(DAY_SECS) * DAY_SECS
+}
+
+/// Seconds per user spent in each status and on each activity, time spent offline left out.
+#[derive(Default, Debug)]
+pub struct Totals {
+    pub statuses: HashMap<(i64, Status), i64>,
+    pub activities: HashMap<(i64, String), i64>,
+}
+
+impl Totals {
+    pub fn add(&mut self, user: i64, status: Status, activity: &str, secs: i64) {
+        if status.is_offline() {
+            return;
+        }
+        *self.statuses.entry((user, status)).or_default() += secs;
+        if !activity.is_empty() {
+            *self
+                .activities
+                .entry((user, activity.to_string()))
+                .or_default() += secs;
+        }
+    }
+
+    /// Seconds each user was online in any status.
+    pub fn online(&self) -> HashMap<i64, i64> {
+        let mut online: HashMap<i64, i64> = HashMap::new();
+        for ((user, _), secs) in &self.statuses {
+            *online.entry(*user).or_default() += secs;
+        }
+        online.retain(|_, secs| *secs > 0);
+        online
+    }
+
+    /// Seconds spent on each activity by all users together.
+    pub fn playtime(&self) -> HashMap<String, i64> {
+        let mut playtime: HashMap<String, i64> = HashMap::new();
+        for ((_, activity), secs) in &self.activities {
+            *playtime.entry(activity.clone()).or_default() += secs;
+        }
+        playtime.retain(|_, secs| *secs > 0);
+        playtime
+    }
+}
+
+/// Seconds per day and guild, per user and status and per user and activity.
+#[derive(Default)]
+struct DailyTotals {
+    statuses: HashMap<(i64, i64, i64, Status), i64>,
+    activities: HashMap<(i64, i64, i64, i32), i64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = daily_status)]
+struct DailyStatus {
+    day: i64,
+    guild_id: i64,
+    user_id: i64,
+    status: Status,
+    secs: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = daily_activity)]
+struct DailyActivity {
+    day: i64,
+    guild_id: i64,
+    user_id: i64,
+    activity_id: i32,
+    secs: i64,
+}
+
+/// Splits `[start, end)` at UTC midnights, leaving out the sorted `gaps`. Returns the
+/// seconds of every day it covers.
+fn secs_per_day(start: i64, end: i64, gaps: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut days: Vec<(i64, i64)> = Vec::new();
+    let mut add = |from: i64, to: i64| {
+        let mut time = from;
+        while time < to {
+            let day = day_start(time);
+            let next = (day + DAY_SECS).min(to);
+            match days.last_mut() {
+                Some((last, secs)) if *last == day => *secs += next - time,
+                _ => days.push((day, next - time)),
+            }
+            time = next;
+        }
+    };
+    let mut time = start;
+    let first_gap = gaps.partition_point(|(_, gap_end)| *gap_end <= start);
+    for (gap_start, gap_end) in &gaps[first_gap..] {
+        if *gap_start >= end {
+            break;
+        }
+        add(time, (*gap_start).min(end));
+        time = time.max(*gap_end);
+    }
+    add(time, end);
+    days
+}
+
+/// The logs of `user`, only the ones of `guild` when given.
+fn user_logs(user: i64, guild: Option<i64>) -> logs::BoxedQuery<'static, Backend> {
+    let query = logs::table.filter(logs::user_id.eq(user)).into_boxed();
+    match guild {
+        Some(guild) => query.filter(logs::guild_id.eq(guild)),
+        None => query,
+    }
+}
+
+/// The latest of `logs` at or before `time`, and the time of the first one after it.
+fn neighbours(
+    conn: &mut DbConnection,
+    logs: impl Fn() -> logs::BoxedQuery<'static, Backend>,
+    time: i64,
+) -> QueryResult<(Option<LogRow>, Option<i64>)> {
+    let previous = logs()
+        .filter(logs::unix_time.le(time))
+        .order((logs::unix_time.desc(), logs::id.desc()))
+        .select(LogRow::as_select())
+        .first(conn)
+        .optional()?;
+    let next = logs()
+        .filter(logs::unix_time.gt(time))
+        .select(diesel::dsl::min(logs::unix_time))
+        .first(conn)?;
+    Ok((previous, next))
+}
+
+/// Moves the end of the session of `previous` to `time`, from `next` or, when it was the
+/// last log, from the time it was rolled up to. Nothing past the limit of `state` changes.
+fn cut_short(
+    conn: &mut DbConnection,
+    previous: &LogRow,
+    next: Option<i64>,
+    time: i64,
+    state: State,
+) -> QueryResult<()> {
+    let end = next
+        .unwrap_or(state.sealed_until.max(previous.unix_time))
+        .min(state.limit());
+    let time = time.min(state.limit());
+    if time < end {
+        roll_up(conn, previous, time, end, -1)
+    } else {
+        roll_up(conn, previous, end, time, 1)
+    }
+}
+
+/// Adds `[start, end)` of the session of `row` to the rollups, or takes it away when `sign`
+/// is negative.
+fn roll_up(
+    conn: &mut DbConnection,
+    row: &LogRow,
+    start: i64,
+    end: i64,
+    sign: i64,
+) -> QueryResult<()> {
+    if row.status.is_offline() || start >= end {
+        return Ok(());
+    }
+    let (runs, first) = load_uptime(conn, start, end)?;
+    let gaps = gaps_between(&runs, first, start, end);
+    let guild = row.guild_id.unwrap_or(NO_GUILD);
+    for (day, secs) in secs_per_day(start, end, &gaps) {
+        diesel::insert_into(daily_status::table)
+            .values(&DailyStatus {
+                day,
+                guild_id: guild,
+                user_id: row.user_id,
+                status: row.status,
+                secs: sign * secs,
+            })
+            .on_conflict((
+                daily_status::day,
+                daily_status::guild_id,
+                daily_status::user_id,
+                daily_status::status,
+            ))
+            .do_update()
+            .set(daily_status::secs.eq(daily_status::secs + excluded(daily_status::secs)))
+            .execute(conn)?;
+        diesel::insert_into(daily_activity::table)
+            .values(&DailyActivity {
+                day,
+                guild_id: guild,
+                user_id: row.user_id,
+                activity_id: row.activity_id,
+                secs: sign * secs,
+            })
+            .on_conflict((
+                daily_activity::day,
+                daily_activity::guild_id,
+                daily_activity::user_id,
+                daily_activity::activity_id,
+            ))
+            .do_update()
+            .set(daily_activity::secs.eq(daily_activity::secs + excluded(daily_activity::secs)))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Where the rollups stand, `None` until they are first built. PostgreSQL keeps the row
+/// locked until the transaction ends, SQLite only lets one writer in anyway.
+pub(super) fn state(conn: &mut DbConnection) -> QueryResult<Option<State>> {
+    let query = rollup_state::table.select(State::as_select());
+    #[cfg(feature = "postgres")]
+    let query = query.for_update();
+    query.first(conn).optional()
+}
+
+/// The time up to which the rollups are complete, `None` until they are first built.
+pub(super) fn sealed_until(conn: &mut DbConnection) -> QueryResult<Option<i64>> {
+    Ok(state(conn)?.map(|state| state.sealed_until))
+}
+
+fn save_state(conn: &mut DbConnection, sealed: i64, rebuilding: bool) -> QueryResult<()> {
+    let values = (
+        rollup_state::sealed_until.eq(sealed),
+        rollup_state::rebuilding.eq(rebuilding),
+    );
+    diesel::insert_into(rollup_state::table)
+        .values((rollup_state::id.eq(1), values))
+        .on_conflict(rollup_state::id)
+        .do_update()
+        .set(values)
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Rolls up the session `row` starts and cuts short the ones of the logs before it. Has to
+/// run before `row` is inserted, in the same transaction.
+pub(super) fn add_log(conn: &mut DbConnection, row: &LogRow, state: State) -> QueryResult<()> {
+    let time = row.unix_time;
+    let (previous, next_any) = neighbours(conn, || user_logs(row.user_id, None), time)?;
+    // A log without a guild is followed by the next log in any guild
+    if let Some(previous) = previous.filter(|previous| previous.guild_id.is_none()) {
+        cut_short(conn, &previous, next_any, time, state)?;
+    }
+    let next = match row.guild_id {
+        Some(guild) => {
+            let (previous, next) = neighbours(conn, || user_logs(row.user_id, Some(guild)), time)?;
+            if let Some(previous) = previous {
+                cut_short(conn, &previous, next, time, state)?;
+            }
+            next
+        }
+        None => next_any,
+    };
+    let end = next.unwrap_or(state.sealed_until.max(time));
+    roll_up(conn, row, time, end.min(state.limit()), 1)
+}
+
+/// The logs before `before` that no log before `followed_before` follows, unless the user
+/// is offline. Sorted by user and time.
+fn open_sessions(
+    conn: &mut DbConnection,
+    before: i64,
+    followed_before: i64,
+) -> QueryResult<Vec<LogRow>> {
+    diesel::alias!(logs as later: LaterLogs);
+    logs::table
+        .filter(logs::unix_time.lt(before))
+        .filter(logs::status.ne_all([Status::Offline, Status::Invisible]))
+        .filter(diesel::dsl::not(diesel::dsl::exists(
+            later
+                .filter(later.field(logs::user_id).eq(logs::user_id))
+                .filter(later.field(logs::unix_time).lt(followed_before))
+                .filter(
+                    later
+                        .field(logs::guild_id)
+                        .eq(logs::guild_id)
+                        .or(logs::guild_id.is_null()),
+                )
+                .filter(
+                    later.field(logs::unix_time).gt(logs::unix_time).or(later
+                        .field(logs::unix_time)
+                        .eq(logs::unix_time)
+                        .and(later.field(logs::id).gt(logs::id))),
+                ),
+        )))
+        .order((logs::user_id.asc(), logs::unix_time.asc(), logs::id.asc()))
+        .select(LogRow::as_select())
+        .load(conn)
+}
+
+/// Rolls up the sessions still open to the last UTC midnight before `now`. Does nothing
+/// while the rollups are yet to be built or being rebuilt, see `complete_rollups`.
+pub fn seal_rollups(now: i64) -> Result<(), StorageError> {
+    run(|conn| conn.transaction(|conn| seal(conn, now)))
+}
+
+fn seal(conn: &mut DbConnection, now: i64) -> QueryResult<()> {
+    let midnight = day_start(now);
+    if let Some(State {
+        sealed_until: sealed,
+        rebuilding: false,
+    }) = state(conn)?
+    {
+        if sealed < midnight {
+            for row in open_sessions(conn, midnight, i64::MAX)? {
+                roll_up(conn, &row, row.unix_time.max(sealed), midnight, 1)?;
+            }
+            save_state(conn, midnight, false)?;
+        }
+    }
+    Ok(())
+}
+
+/// Builds the rollups from scratch when there are none yet or finishes an interrupted
+/// rebuild. Returns the number of logs and of rows rolled up.
+pub fn complete_rollups(now: i64) -> Result<(usize, usize), StorageError> {
+    match run(|conn| conn.transaction(state))? {
+        None => rebuild_rollups(now),
+        Some(state) if state.rebuilding => continue_rebuild(now),
+        Some(_) => Ok((0, 0)),
+    }
+}
+
+/// The offline gaps of the bot between `from` and `to`.
+fn gaps(conn: &mut DbConnection, from: i64, to: i64) -> QueryResult<Vec<(i64, i64)>> {
+    let (runs, first) = load_uptime(conn, from, to)?;
+    Ok(gaps_between(&runs, first, from, to))
+}
+
+/// The logs in `[from, to)` after the ones still going on at `from`, sorted by user and time.
+/// Returns how many of them are in `[from, to)` too.
+fn logs_from(conn: &mut DbConnection, from: i64, to: i64) -> QueryResult<(Vec<LogRow>, usize)> {
+    let mut rows = open_sessions(conn, from, from)?;
+    let logs: Vec<LogRow> = logs::table
+        .filter(logs::unix_time.ge(from))
+        .filter(logs::unix_time.lt(to))
+        .order((logs::user_id.asc(), logs::unix_time.asc(), logs::id.asc()))
+        .select(LogRow::as_select())
+        .load(conn)?;
+    let count = logs.len();
+    rows.extend(logs);
+    // Stable, so the logs of every user stay in order
+    rows.sort_by_key(|row| row.user_id);
+    Ok((rows, count))
+}
+
+/// Adds up the sessions of `rows`, sorted by user and time, from `from` on. The sessions
+/// nothing follows last until `open_until`, or are left out without it.
+fn sum_sessions(
+    rows: &[LogRow],
+    from: i64,
+    open_until: Option<i64>,
+    gaps: &[(i64, i64)],
+) -> DailyTotals {
+    let mut totals = DailyTotals::default();
+    // Walking back through the logs of a user, the next log in any guild and in each guild
+    let mut user = None;
+    let mut next_any: Option<i64> = None;
+    let mut next_in_guild: HashMap<i64, i64> = HashMap::new();
+    for row in rows.iter().rev() {
+        if user != Some(row.user_id) {
+            user = Some(row.user_id);
+            next_any = None;
+            next_in_guild.clear();
+        }
+        let next = match row.guild_id {
+            Some(guild) => next_in_guild.insert(guild, row.unix_time),
+            None => next_any,
+        };
+        next_any = Some(row.unix_time);
+        let Some(end) = next.or(open_until) else {
+            continue;
+        };
+        if row.status.is_offline() {
+            continue;
+        }
+        let guild = row.guild_id.unwrap_or(NO_GUILD);
+        for (day, secs) in secs_per_day(row.unix_time.max(from), end, gaps) {
+            *totals
+                .statuses
+                .entry((day, guild, row.user_id, row.status))
+                .or_default() += secs;
+            *totals
+                .activities
+                .entry((day, guild, row.user_id, row.activity_id))
+                .or_default() += secs;
+        }
+    }
+    totals
+}
+
+/// Inserts the rows of days that aren't rolled up yet, returns how many there were.
+fn insert_totals(conn: &mut DbConnection, totals: DailyTotals) -> QueryResult<usize> {
+    let statuses: Vec<DailyStatus> = totals
+        .statuses
+        .into_iter()
+        .map(|((day, guild_id, user_id, status), secs)| DailyStatus {
+            day,
+            guild_id,
+            user_id,
+            status,
+            secs,
+        })
+        .collect();
+    for batch in statuses.chunks(BATCH_SIZE) {
+        diesel::insert_into(daily_status::table)
+            .values(batch)
+            .execute(conn)?;
+    }
+    let activities: Vec<DailyActivity> = totals
+        .activities
+        .into_iter()
+        .map(
+            |((day, guild_id, user_id, activity_id), secs)| DailyActivity {
+                day,
+                guild_id,
+                user_id,
+                activity_id,
+                secs,
+            },
+        )
+        .collect();
+    for batch in activities.chunks(BATCH_SIZE) {
+        diesel::insert_into(daily_activity::table)
+            .values(batch)
+            .execute(conn)?;
+    }
+    Ok(statuses.len() + activities.len())
+}
+
+/// Throws away the rollups and starts rebuilding them from the day of the first log.
+fn start_rebuild(conn: &mut DbConnection, now: i64) -> QueryResult<()> {
+    diesel::delete(daily_status::table).execute(conn)?;
+    diesel::delete(daily_activity::table).execute(conn)?;
+    let first: Option<i64> = logs::table
+        .select(diesel::dsl::min(logs::unix_time))
+        .first(conn)?;
+    save_state(conn, first.map(day_start).unwrap_or(day_start(now)), true)
+}
+
+/// Rolls up the next day of a rebuild. Once it reached the last UTC midnight before `now`,
+/// rolls up the sessions that ended since and completes it. Returns the number of logs and
+/// of rows rolled up, `None` when there is no rebuild to continue.
+fn rebuild_step(conn: &mut DbConnection, now: i64) -> QueryResult<Option<(usize, usize)>> {
+    let Some(State {
+        sealed_until: day,
+        rebuilding: true,
+    }) = state(conn)?
+    else {
+        return Ok(None);
+    };
+    if day < day_start(now) {
+        let end = day + DAY_SECS;
+        let (rows, count) = logs_from(conn, day, end)?;
+        let gaps = gaps(conn, day, end)?;
+        let inserted = insert_totals(conn, sum_sessions(&rows, day, Some(end), &gaps))?;
+        save_state(conn, end, true)?;
+        return Ok(Some((count, inserted)));
+    }
+
+    let (rows, count) = logs_from(conn, day, i64::MAX)?;
+    let last = rows.iter().map(|row| row.unix_time).max().unwrap_or(day);
+    let gaps = gaps(conn, day, last.max(day))?;
+    let inserted = insert_totals(conn, sum_sessions(&rows, day, None, &gaps))?;
+    save_state(conn, day, false)?;
+    Ok(Some((count, inserted)))
+}
+
+/// Throws away the rollups and rolls up every log again, up to the last UTC midnight
+/// before `now`, one day per transaction. Returns the number of logs and of rows rolled up.
+pub fn rebuild(conn: &mut DbConnection, now: i64) -> QueryResult<(usize, usize)> {
+    conn.transaction(|conn| start_rebuild(conn, now))?;
+    let mut total = (0, 0);
+    while let Some((logs, rows)) = conn.transaction(|conn| rebuild_step(conn, now))? {
+        total = (total.0 + logs, total.1 + rows);
+    }
+    Ok(total)
+}
+
+pub fn rebuild_rollups(now: i64) -> Result<(usize, usize), StorageError> {
+    run(|conn| conn.transaction(|conn| start_rebuild(conn, now)))?;
+    continue_rebuild(now)
+}
+
+/// Rolls up the days left to rebuild, each in a short transaction of its own so the logs
+/// recorded meanwhile don't wait for the whole rebuild.
+fn continue_rebuild(now: i64) -> Result<(usize, usize), StorageError> {
+    let mut total = (0, 0);
+    while let Some((logs, rows)) = run(|conn| conn.transaction(|conn| rebuild_step(conn, now)))? {
+        total = (total.0 + logs, total.1 + rows);
+    }
+    Ok(total)
+}
+
+/// Totals of the UTC days in `[from, to)` of the users of `guild`, or only of `users`.
+pub(super) fn load_totals(
+    conn: &mut DbConnection,
+    guild: Option<i64>,
+    users: Option<&[i64]>,
+    from: i64,
+    to: i64,
+) -> QueryResult<Totals> {
+    let mut statuses = daily_status::table
+        .filter(daily_status::day.ge(from))
+        .filter(daily_status::day.lt(to))
+        .into_boxed();
+    let mut activities = daily_activity::table
+        .inner_join(activity_names::table)
+        .filter(daily_activity::day.ge(from))
+        .filter(daily_activity::day.lt(to))
+        .filter(activity_names::canonical.ne(""))
+        .into_boxed();
+    if let Some(guild) = guild {
+        statuses = statuses.filter(daily_status::guild_id.eq_any([guild, NO_GUILD]));
+        activities = activities.filter(daily_activity::guild_id.eq_any([guild, NO_GUILD]));
+    }
+    if let Some(users) = users {
+        statuses = statuses.filter(daily_status::user_id.eq_any(users.to_vec()));
+        activities = activities.filter(daily_activity::user_id.eq_any(users.to_vec()));
+    }
+
+    let mut totals = Totals::default();
+    let rows: Vec<(i64, Status, i64)> = statuses
+        .select((
+            daily_status::user_id,
+            daily_status::status,
+            daily_status::secs,
+        ))
+        .load(conn)?;
+    for (user, status, secs) in rows {
+        *totals.statuses.entry((user, status)).or_default() += secs;
+    }
+    let rows: Vec<(i64, String, i64)> = activities
+        .select((
+            daily_activity::user_id,
+            activity_names::canonical,
+            daily_activity::secs,
+        ))
+        .load(conn)?;
+    for (user, activity, secs) in rows {
+        *totals.activities.entry((user, activity)).or_default() += secs;
+    }
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::prelude::*;
+    use diesel_migrations::MigrationHarness;
+
+    use super::{rebuild, save_state, seal, DAY_SECS};
+    use crate::migrate::MIGRATIONS;
+    use crate::schema::{daily_activity, daily_status, uptime};
+    use crate::storage::Status::{self, DoNotDisturb, Idle, Offline, Online};
+    use crate::storage::{insert_logs, DbConnection, NewLog};
+
+    const DAY: i64 = 1_760_000_000 / DAY_SECS * DAY_SECS;
+    const HOUR: i64 = 60 * 60;
+
+    type Rollups = (
+        Vec<(i64, i64, i64, Status, i64)>,
+        Vec<(i64, i64, i64, i32, i64)>,
+    );
+
+    /// A fresh SQLite database in memory. PostgreSQL uses the one at `DATABASE_URL`, emptied
+    /// within a transaction that is never committed, so the tests needing one are ignored by
+    /// default and have to run one at a time:
+    ///
+    /// ```text
+    /// DATABASE_URL=postgres://localhost/presence_test cargo test --features postgres -- --ignored --test-threads=1
+    /// ```
+    fn connection() -> DbConnection {
+        #[cfg(not(feature = "postgres"))]
+        let mut conn = DbConnection::establish(":memory:").unwrap();
+        #[cfg(feature = "postgres")]
+        let mut conn = crate::storage::establish_connection().unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        conn.begin_test_transaction().unwrap();
+        #[cfg(feature = "postgres")]
+        for table in [
+            "logs",
+            "uptime",
+            "daily_status",
+            "daily_activity",
+            "rollup_state",
+        ] {
+            diesel::sql_query(format!("DELETE FROM {}", table))
+                .execute(&mut conn)
+                .unwrap();
+        }
+        conn
+    }
+
+    fn rollups(conn: &mut DbConnection) -> Rollups {
+        let statuses = daily_status::table
+            .filter(daily_status::secs.ne(0))
+            .select((
+                daily_status::day,
+                daily_status::guild_id,
+                daily_status::user_id,
+                daily_status::status,
+                daily_status::secs,
+            ))
+            .order((
+                daily_status::day,
+                daily_status::guild_id,
+                daily_status::user_id,
+                daily_status::status,
+            ))
+            .load(conn)
+            .unwrap();
+        let activities = daily_activity::table
+            .filter(daily_activity::secs.ne(0))
+            .select((
+                daily_activity::day,
+                daily_activity::guild_id,
+                daily_activity::user_id,
+                daily_activity::activity_id,
+                daily_activity::secs,
+            ))
+            .order((
+                daily_activity::day,
+                daily_activity::guild_id,
+                daily_activity::user_id,
+                daily_activity::activity_id,
+            ))
+            .load(conn)
+            .unwrap();
+        (statuses, activities)
+    }
+
+    fn log(
+        user_id: i64,
+        guild_id: Option<i64>,
+        status: Status,
+        activity: &str,
+        time: i64,
+    ) -> NewLog {
+        NewLog {
+            user_id,
+            status,
+            activity: activity.to_string(),
+            unix_time: time,
+            guild_id,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "postgres", ignore)]
+    fn rolling_up_logs_as_they_come_matches_a_rebuild() {
+        let conn = &mut connection();
+        // The bot was offline for two hours on the second day
+        diesel::insert_into(uptime::table)
+            .values(&[
+                (
+                    uptime::started_at.eq(DAY),
+                    uptime::last_seen.eq(DAY + 30 * HOUR),
+                ),
+                (
+                    uptime::started_at.eq(DAY + 32 * HOUR),
+                    uptime::last_seen.eq(DAY + 4 * DAY_SECS),
+                ),
+            ])
+            .execute(conn)
+            .unwrap();
+        save_state(conn, DAY, false).unwrap();
+
+        // Out of order, backfilled logs cut short the sessions already rolled up
+        let logs = [
+            log(1, Some(5), Online, "Dota 2", DAY + HOUR),
+            log(1, Some(5), Idle, "", DAY + 23 * HOUR),
+            log(2, None, Online, "CS2", DAY + 2 * HOUR),
+            log(2, Some(6), DoNotDisturb, "CS2", DAY + 20 * HOUR),
+            log(1, Some(5), Offline, "", DAY + 40 * HOUR),
+            log(1, Some(6), Online, "Dota 2", DAY + 10 * HOUR),
+            log(2, Some(6), Offline, "", DAY + 50 * HOUR),
+            log(1, Some(5), DoNotDisturb, "Dota 2", DAY + 12 * HOUR),
+            log(2, None, Idle, "", DAY + 5 * HOUR),
+            log(1, Some(5), Online, "", DAY + 60 * HOUR),
+        ];
+        for (sealed, log) in logs.into_iter().enumerate() {
+            insert_logs(conn, &[log]).unwrap();
+            // The scheduler seals midnights in between
+            if sealed == 3 || sealed == 6 {
+                seal(conn, DAY + (sealed as i64 / 3) * DAY_SECS + HOUR).unwrap();
+            }
+        }
+        let now = DAY + 3 * DAY_SECS + HOUR;
+        seal(conn, now).unwrap();
+        let incremental = rollups(conn);
+        assert!(!incremental.0.is_empty());
+
+        rebuild(conn, now).unwrap();
+        assert_eq!(incremental, rollups(conn));
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use super::{
+    add_activity_alias, add_alert_rule, add_watch, apply_activity_aliases, delete_activity_alias,
+    delete_alert_rule, delete_board, delete_digest, delete_watch, get_activity_aliases,
+    get_alert_history, get_alert_rules, get_audit, get_boards, get_digest, get_guild_timezone,
+    get_user_timezone, get_watches_by_subscriber, is_opted_out, opt_in, opt_out, save_board,
+    save_digest, set_board_message, set_guild_timezone, set_user_timezone, ActivityAlias,
+    AlertHistory, AlertRule, Audit, Board, DatabaseStore, Digest, NewActivityAlias, NewAlertRule,
+    NewBoard, NewWatch, StorageError, Watch,
+};
+
+pub type SharedSettingsStore = Arc<dyn SettingsStore>;
+
+/// Everything the commands keep besides the presence logs: aliases, boards, watches,
+/// opt-outs, alert rules, digests, time zones and the audit log. Commands only go through
+/// this trait and `LogStore`, as does the board task. The other background tasks still use the
+/// free functions behind it.
+pub trait SettingsStore: Send + Sync {
+    fn add_activity_alias(&self, alias: NewActivityAlias) -> Result<(), StorageError>;
+
+    fn get_activity_aliases(&self) -> Result<Vec<ActivityAlias>, StorageError>;
+
+    fn delete_activity_alias(&self, id: i32) -> Result<bool, StorageError>;
+
+    /// Renames the recorded activities after the aliases, returns how many changed.
+    fn apply_activity_aliases(&self) -> Result<usize, StorageError>;
+
+    fn get_boards(&self) -> Result<Vec<Board>, StorageError>;
+
+    fn save_board(&self, board: NewBoard) -> Result<(), StorageError>;
+
+    fn set_board_message(&self, guild_id: i64, message_id: Option<i64>)
+        -> Result<(), StorageError>;
+
+    fn delete_board(&self, guild_id: i64) -> Result<bool, StorageError>;
+
+    fn add_watch(&self, watch: NewWatch) -> Result<(), StorageError>;
+
+    fn get_watches_by_subscriber(
+        &self,
+        guild_id: i64,
+        subscriber_id: i64,
+    ) -> Result<Vec<Watch>, StorageError>;
+
+    fn delete_watch(&self, id: i32, subscriber_id: i64) -> Result<bool, StorageError>;
+
+    fn is_opted_out(&self, user_id: i64) -> Result<bool, StorageError>;
+
+    fn opt_out(&self, user_id: i64, time: i64) -> Result<(), StorageError>;
+
+    fn opt_in(&self, user_id: i64) -> Result<bool, StorageError>;
+
+    fn add_alert_rule(&self, rule: NewAlertRule) -> Result<(), StorageError>;
+
+    fn get_alert_rules(&self, guild_id: Option<i64>) -> Result<Vec<AlertRule>, StorageError>;
+
+    fn delete_alert_rule(&self, id: i32, guild_id: i64) -> Result<bool, StorageError>;
+
+    fn get_alert_history(
+        &self,
+        guild_id: Option<i64>,
+        since: i64,
+        limit: i64,
+    ) -> Result<Vec<AlertHistory>, StorageError>;
+
+    fn save_digest(&self, digest: Digest) -> Result<(), StorageError>;
+
+    fn get_digest(&self, guild_id: i64) -> Result<Option<Digest>, StorageError>;
+
+    fn delete_digest(&self, guild_id: i64) -> Result<bool, StorageError>;
+
+    fn get_guild_timezone(&self, guild_id: i64) -> Result<Option<String>, StorageError>;
+
+    fn set_guild_timezone(
+        &self,
+        guild_id: i64,
+        timezone: Option<String>,
+    ) -> Result<(), StorageError>;
+
+    fn get_user_timezone(&self, user_id: i64) -> Result<Option<String>, StorageError>;
+
+    fn set_user_timezone(&self, user_id: i64, timezone: Option<String>)
+        -> Result<(), StorageError>;
+
+    fn get_audit(
+        &self,
+        guild_id: i64,
+        invoker: Option<i64>,
+        target: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<Audit>, StorageError>;
+
+    /// Snapshots the database into the backup directory, returns the snapshot.
+    #[cfg(not(feature = "postgres"))]
+    fn backup(&self) -> Result<std::path::PathBuf, String>;
+}
+
+impl SettingsStore for DatabaseStore {
+    fn add_activity_alias(&self, alias: NewActivityAlias) -> Result<(), StorageError> {
+        add_activity_alias(alias)
+    }
+
+    fn get_activity_aliases(&self) -> Result<Vec<ActivityAlias>, StorageError> {
+        get_activity_aliases()
+    }
+
+    fn delete_activity_alias(&self, id: i32) -> Result<bool, StorageError> {
+        delete_activity_alias(id)
+    }
+
+    fn apply_activity_aliases(&self) -> Result<usize, StorageError> {
+        apply_activity_aliases()
+    }
+
+    fn get_boards(&self) -> Result<Vec<Board>, StorageError> {
+        get_boards()
+    }
+
+    fn save_board(&self, board: NewBoard) -> Result<(), StorageError> {
+        save_board(board)
+    }
+
+    fn set_board_message(
+        &self,
+        guild_id: i64,
+        message_id: Option<i64>,
+    ) -> Result<(), StorageError> {
+        set_board_message(guild_id, message_id)
+    }
+
+    fn delete_board(&self, guild_id: i64) -> Result<bool, StorageError> {
+        delete_board(guild_id)
+    }
+
+    fn add_watch(&self, watch: NewWatch) -> Result<(), StorageError> {
+        add_watch(watch)
+    }
+
+    fn get_watches_by_subscriber(
+        &self,
+        guild_id: i64,
+        subscriber_id: i64,
+    ) -> Result<Vec<Watch>, StorageError> {
+        get_watches_by_subscriber(guild_id, subscriber_id)
+    }
+
+    fn delete_watch(&self, id: i32, subscriber_id: i64) -> Result<bool, StorageError> {
+        delete_watch(id, subscriber_id)
+    }
+
+    fn is_opted_out(&self, user_id: i64) -> Result<bool, StorageError> {
+        is_opted_out(user_id)
+    }
+
+    fn opt_out(&self, user_id: i64, time: i64) -> Result<(), StorageError> {
+        opt_out(user_id, time)
+    }
+
+    fn opt_in(&self, user_id: i64) -> Result<bool, StorageError> {
+        opt_in(user_id)
+    }
+
+    fn add_alert_rule(&self, rule: NewAlertRule) -> Result<(), StorageError> {
+        add_alert_rule(rule)
+    }
+
+    fn get_alert_rules(&self, guild_id: Option<i64>) -> Result<Vec<AlertRule>, StorageError> {
+        get_alert_rules(guild_id)
+    }
+
+    fn delete_alert_rule(&self, id: i32, guild_id: i64) -> Result<bool, StorageError> {
+        delete_alert_rule(id, guild_id)
+    }
+
+    fn get_alert_history(
+        &self,
+        guild_id: Option<i64>,
+        since: i64,
+        limit: i64,
+    ) -> Result<Vec<AlertHistory>, StorageError> {
+        get_alert_history(guild_id, since, limit)
+    }
+
+    fn save_digest(&self, digest: Digest) -> Result<(), StorageError> {
+        save_digest(digest)
+    }
+
+    fn get_digest(&self, guild_id: i64) -> Result<Option<Digest>, StorageError> {
+        get_digest(guild_id)
+    }
+
+    fn delete_digest(&self, guild_id: i64) -> Result<bool, StorageError> {
+        delete_digest(guild_id)
+    }
+
+    fn get_guild_timezone(&self, guild_id: i64) -> Result<Option<String>, StorageError> {
+        get_guild_timezone(guild_id)
+    }
+
+    fn set_guild_timezone(
+        &self,
+        guild_id: i64,
+        timezone: Option<String>,
+    ) -> Result<(), StorageError> {
+        set_guild_timezone(guild_id, timezone)
+    }
+
+    fn get_user_timezone(&self, user_id: i64) -> Result<Option<String>, StorageError> {
+        get_user_timezone(user_id)
+    }
+
+    fn set_user_timezone(
+        &self,
+        user_id: i64,
+        timezone: Option<String>,
+    ) -> Result<(), StorageError> {
+        set_user_timezone(user_id, timezone)
+    }
+
+    fn get_audit(
+        &self,
+        guild_id: i64,
+        invoker: Option<i64>,
+        target: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<Audit>, StorageError> {
+        get_audit(guild_id, invoker, target, limit)
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    fn backup(&self) -> Result<std::path::PathBuf, String> {
+        crate::backup::backup(&crate::backup::backup_dir())
+    }
+}
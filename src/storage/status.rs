@@ -0,0 +1,142 @@
+//! The status of a log, stored as a small integer code.
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::SmallInt;
+use serde::{Serialize, Serializer};
+use serenity::all::OnlineStatus;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, AsExpression, FromSqlRow)]
+#[diesel(sql_type = SmallInt)]
+pub enum Status {
+    /// A status Discord added after this code was written, or a code this build doesn't know.
+    Unknown,
+    Online,
+    Idle,
+    DoNotDisturb,
+    Offline,
+    /// Only known for imported logs, the gateway reports invisible users as offline.
+    Invisible,
+}
+
+impl Status {
+    /// The statuses users can filter by.
+    pub const CHOICES: [Status; 5] = [
+        Status::Online,
+        Status::Idle,
+        Status::DoNotDisturb,
+        Status::Offline,
+        Status::Invisible,
+    ];
+
+    /// Statuses as Discord names them, the names logs used before they were stored as codes.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Unknown => "unknown",
+            Status::Online => "online",
+            Status::Idle => "idle",
+            Status::DoNotDisturb => "dnd",
+            Status::Offline => "offline",
+            Status::Invisible => "invisible",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Unknown => "Unknown",
+            Status::Online => "Online",
+            Status::Idle => "Idle",
+            Status::DoNotDisturb => "Do not disturb",
+            Status::Offline => "Offline",
+            Status::Invisible => "Invisible",
+        }
+    }
+
+    /// Whether the user appears offline to others.
+    pub fn is_offline(self) -> bool {
+        matches!(self, Status::Offline | Status::Invisible)
+    }
+
+    /// The codes in the `status` column, the migration converting the names uses the same.
+    fn code(self) -> &'static i16 {
+        match self {
+            Status::Unknown => &0,
+            Status::Online => &1,
+            Status::Idle => &2,
+            Status::DoNotDisturb => &3,
+            Status::Offline => &4,
+            Status::Invisible => &5,
+        }
+    }
+
+    fn from_code(code: i16) -> Self {
+        match code {
+            1 => Status::Online,
+            2 => Status::Idle,
+            3 => Status::DoNotDisturb,
+            4 => Status::Offline,
+            5 => Status::Invisible,
+            _ => Status::Unknown,
+        }
+    }
+}
+
+impl From<OnlineStatus> for Status {
+    fn from(status: OnlineStatus) -> Self {
+        match status {
+            OnlineStatus::Online => Status::Online,
+            OnlineStatus::Idle => Status::Idle,
+            OnlineStatus::DoNotDisturb => Status::DoNotDisturb,
+            OnlineStatus::Offline => Status::Offline,
+            OnlineStatus::Invisible => Status::Invisible,
+            _ => Status::Unknown,
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        [Status::Unknown]
+            .into_iter()
+            .chain(Status::CHOICES)
+            .find(|status| status.as_str().eq_ignore_ascii_case(value.trim()))
+            .ok_or(format!("unknown status {}", value))
+    }
+}
+
+impl Serialize for Status {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<DB: Backend> ToSql<SmallInt, DB> for Status
+where
+    i16: ToSql<SmallInt, DB>,
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        self.code().to_sql(out)
+    }
+}
+
+impl<DB: Backend> FromSql<SmallInt, DB> for Status
+where
+    i16: FromSql<SmallInt, DB>,
+{
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+        i16::from_sql(bytes).map(Status::from_code)
+    }
+}
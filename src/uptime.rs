@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use crate::sessions::unix_now;
+use crate::storage::{start_uptime, touch_uptime, Uptime};
+
+/// How often the current run is marked as alive. A run is assumed to have lasted until
+/// one interval after its last heartbeat.
+pub const HEARTBEAT_SECS: i64 = 60;
+
+/// Records the start of this run and keeps its last heartbeat up to date, which lets the
+/// statistics tell apart "nothing happened" from "the bot wasn't there to see it".
+pub fn spawn() {
+    let run = match start_uptime(unix_now()) {
+        Ok(run) => run,
+        Err(err) => {
+            println!("Error while recording the bot start: {}", err);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_SECS as u64));
+        loop {
+            interval.tick().await;
+            if let Err(err) = touch_uptime(run, unix_now()) {
+                println!("Error while recording a heartbeat: {}", err);
+            }
+        }
+    });
+}
+
+/// The gaps of `[from, to)` given the runs overlapping it ordered by start and the start of
+/// the very first run.
+pub fn gaps_between(runs: &[Uptime], first: Option<i64>, from: i64, to: i64) -> Vec<(i64, i64)> {
+    let Some(first) = first else {
+        return Vec::new();
+    };
+
+    let mut gaps = Vec::new();
+    let mut covered_until = from.max(first).min(to);
+    let now = unix_now();
+    for run in runs {
+        if run.started_at > covered_until {
+            gaps.push((covered_until, run.started_at.min(to)));
+        }
+        let end = (run.last_seen + HEARTBEAT_SECS).min(now);
+        covered_until = covered_until.max(end);
+    }
+    // The running bot always has a fresh heartbeat, anything after it hasn't happened yet
+    if covered_until < to.min(now) {
+        gaps.push((covered_until, to.min(now)));
+    }
+    gaps
+}
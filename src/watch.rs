@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use serenity::all::{ChannelId, Context, CreateMessage, GuildId, UserId};
+
+use crate::aliases;
+use crate::sessions::unix_now;
+use crate::storage::{
+    get_watches_by_target, is_opted_out, set_watch_notified, LogQuery, LogStore, Status, Watch,
+};
+
+/// Status and canonical activity names of a user as last seen by the bot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PresenceState {
+    pub status: Status,
+    pub activities: Vec<String>,
+}
+
+/// Presences per guild, each guild reports its own presence updates for the same user.
+pub type SharedPresences = Arc<Mutex<HashMap<(GuildId, UserId), PresenceState>>>;
+
+/// Minimum time between two notifications of the same watch, read from `WATCH_COOLDOWN`.
+fn cooldown() -> i64 {
+    env::var("WATCH_COOLDOWN")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(600)
+}
+
+/// Stores the new presence of `user` in `guild` and returns the previous one. After a restart
+/// the previous presence is taken from the last log, so users don't look like they just came
+/// online.
+pub fn swap_presence(
+    presences: &SharedPresences,
+    store: &dyn LogStore,
+    guild: GuildId,
+    user: UserId,
+    current: PresenceState,
+) -> Option<PresenceState> {
+    let previous = presences.lock().unwrap().insert((guild, user), current);
+    if previous.is_some() {
+        return previous;
+    }
+    match store.first(LogQuery::new().user(user.into()).guild(Some(guild.into()))) {
+        Ok(log) => log.map(|log| PresenceState {
+            status: log.status,
+            activities: vec![log.activity],
+        }),
+        Err(err) => {
+            println!("Error while loading the last log: {}", err);
+            None
+        }
+    }
+}
+
+fn matches(watch: &Watch, state: &PresenceState) -> bool {
+    if let Some(status) = watch.status {
+        if status != state.status {
+            return false;
+        }
+    }
+    if let Some(activity) = &watch.activity {
+        let activity = aliases::canonical(activity);
+        return state
+            .activities
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&activity));
+    }
+    watch.status.is_some() || !state.status.is_offline()
+}
+
+pub fn describe(watch: &Watch) -> String {
+    match (&watch.activity, &watch.status) {
+        (Some(activity), Some(status)) => format!("plays {} while {}", activity, status),
+        (Some(activity), None) => format!("starts playing {}", activity),
+        (None, Some(status)) => format!("goes {}", status),
+        (None, None) => "comes online".to_string(),
+    }
+}
+
+/// Notifies the subscribers of every watch on `user` that the presence change satisfied.
+pub async fn notify(
+    ctx: &Context,
+    guild: GuildId,
+    user: UserId,
+    previous: Option<&PresenceState>,
+    current: &PresenceState,
+) {
+    let watches = match get_watches_by_target(guild.into(), user.into()) {
+        Ok(watches) => watches,
+        Err(err) => {
+            println!("Error while loading watches: {}", err);
+            return;
+        }
+    };
+    if watches.is_empty() {
+        return;
+    }
+    // Opting out removes the watches, this only guards against a race with /watch add
+    if is_opted_out(user.into()).unwrap_or(true) {
+        return;
+    }
+
+    let now = unix_now();
+    for watch in watches {
+        if !matches(&watch, current) || previous.is_some_and(|previous| matches(&watch, previous)) {
+            continue;
+        }
+        if let Some(last) = watch.last_notified {
+            if now - last < cooldown() {
+                continue;
+            }
+        }
+
+        let content = format!("Watch #{}: <@{}> {}", watch.id, user, describe(&watch));
+        let sent = match watch.channel_id {
+            Some(channel) => ChannelId::new(channel as u64)
+                .send_message(
+                    &ctx.http,
+                    CreateMessage::new()
+                        .content(format!("<@{}>: {}", watch.subscriber_id, content)),
+                )
+                .await
+                .map(|_| ()),
+            None => UserId::new(watch.subscriber_id as u64)
+                .direct_message(&ctx.http, CreateMessage::new().content(content))
+                .await
+                .map(|_| ()),
+        };
+        match sent {
+            Ok(()) => {
+                if let Err(err) = set_watch_notified(watch.id, now) {
+                    println!("Error while saving a watch notification: {}", err);
+                }
+            }
+            Err(err) => println!("Cannot send a watch notification: {}", err),
+        }
+    }
+}